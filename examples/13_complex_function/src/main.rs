@@ -2,15 +2,275 @@
 //!
 //! Demonstrates autodiff on functions involving complex numbers.
 //! Complex numbers are represented as (real, imag) pairs.
+//! Real-valued functions get Wirtinger derivatives from reverse mode;
+//! complex maps get f'(z) from a forward-mode Jacobian and Cauchy-Riemann.
+//!
+//! Example 26 wraps the same arithmetic in a `Complex` type (see "Why Examples
+//! 13 and 14 Keep Tuples" in the complex types chapter).
 
 #![feature(autodiff)]
-use std::autodiff::autodiff_reverse;
+use std::autodiff::{autodiff_forward, autodiff_reverse};
 
 /// Complex multiplication: (a + bi)(c + di) = (ac - bd) + (ad + bc)i
 fn complex_mul(a_re: f64, a_im: f64, b_re: f64, b_im: f64) -> (f64, f64) {
     (a_re * b_re - a_im * b_im, a_re * b_im + a_im * b_re)
 }
 
+// ---------------------------------------------------------------------------
+// Enzyme-safe real helpers: range reduction plus series, no std calls
+// ---------------------------------------------------------------------------
+
+const PI: f64 = std::f64::consts::PI;
+
+/// exp(x) via range reduction: halve until |x| ≤ 0.5, Taylor, square back.
+/// Infinite x maps straight to 0 or +inf, since halving cannot shrink it.
+fn my_exp(x: f64) -> f64 {
+    if x == f64::NEG_INFINITY {
+        return 0.0;
+    }
+    if x == f64::INFINITY {
+        return x;
+    }
+    let mut r = x;
+    let mut halvings = 0;
+    while (if r < 0.0 { -r } else { r }) > 0.5 {
+        r /= 2.0;
+        halvings += 1;
+    }
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    let mut k = 1;
+    while k < 20 {
+        term *= r / k as f64;
+        sum += term;
+        k += 1;
+    }
+    let mut i = 0;
+    while i < halvings {
+        sum *= sum;
+        i += 1;
+    }
+    sum
+}
+
+/// ln(x) for x > 0: x = m·2ᵏ with m in [1, 2), ln m = 2 atanh((m-1)/(m+1)).
+/// Returns -inf at 0, +inf at +inf and NaN for negative or NaN x, where the
+/// scaling loops would never terminate.
+fn my_ln(x: f64) -> f64 {
+    if x.is_nan() || x < 0.0 {
+        return f64::NAN;
+    }
+    if x == 0.0 {
+        return f64::NEG_INFINITY;
+    }
+    if x == f64::INFINITY {
+        return x;
+    }
+    let mut m = x;
+    let mut k = 0.0;
+    while m >= 2.0 {
+        m /= 2.0;
+        k += 1.0;
+    }
+    while m < 1.0 {
+        m *= 2.0;
+        k -= 1.0;
+    }
+    let s = (m - 1.0) / (m + 1.0);
+    let mut power = s;
+    let mut sum = 0.0;
+    let mut n = 0;
+    while n < 30 {
+        sum += power / (2 * n + 1) as f64;
+        power *= s * s;
+        n += 1;
+    }
+    2.0 * sum + k * std::f64::consts::LN_2
+}
+
+/// sqrt(x) via range reduction into [0.25, 1] and Newton iteration
+fn my_sqrt(x: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    let mut m = x;
+    let mut scale = 1.0;
+    while m > 1.0 {
+        m /= 4.0;
+        scale *= 2.0;
+    }
+    while m < 0.25 {
+        m *= 4.0;
+        scale /= 2.0;
+    }
+    let mut guess = 1.0;
+    let mut i = 0;
+    while i < 8 {
+        guess = (guess + m / guess) / 2.0;
+        i += 1;
+    }
+    guess * scale
+}
+
+/// Shifts x into [-π, π] by subtracting the nearest multiple of 2π. For large
+/// |x| the product k·2π is off by up to ulp(x), so the subtraction repeats
+/// until the result is in range; each pass shrinks |r| by about 2⁻⁵², so even
+/// |x| near f64::MAX takes a handful of passes and the tape stays short. NaN
+/// for non-finite x. Unlike std's exact reduction, the rounded 2π leaves no
+/// correct digits beyond |x| ~ 1e15, but the result is always in [-π, π].
+fn reduce_angle(x: f64) -> f64 {
+    if !x.is_finite() {
+        return f64::NAN;
+    }
+    let mut r = x;
+    while r > PI || r < -PI {
+        let k = (r / (2.0 * PI)).round();
+        r -= k * (2.0 * PI);
+    }
+    r
+}
+
+/// Taylor series sin(x) after reduction to [-π, π]
+fn my_sin(x: f64) -> f64 {
+    let x = reduce_angle(x);
+    let mut sum = x;
+    let mut term = x;
+    let mut k = 1;
+    while k < 30 {
+        term *= -x * x / ((2 * k) as f64 * (2 * k + 1) as f64);
+        sum += term;
+        k += 1;
+    }
+    sum
+}
+
+/// Taylor series cos(x) after reduction to [-π, π]
+fn my_cos(x: f64) -> f64 {
+    let x = reduce_angle(x);
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    let mut k = 1;
+    while k < 30 {
+        term *= -x * x / ((2 * k - 1) as f64 * (2 * k) as f64);
+        sum += term;
+        k += 1;
+    }
+    sum
+}
+
+/// atan(x): fold |x| > 1 onto 1/|x|, halve the angle three times with
+/// t ← t/(1 + √(1 + t²)), then sum the Taylor series
+fn my_atan(x: f64) -> f64 {
+    let sign = if x < 0.0 { -1.0 } else { 1.0 };
+    let mut t = sign * x;
+    let invert = t > 1.0;
+    if invert {
+        t = 1.0 / t;
+    }
+    let mut halvings = 0;
+    while halvings < 3 {
+        t /= 1.0 + my_sqrt(1.0 + t * t);
+        halvings += 1;
+    }
+    let mut sum = 0.0;
+    let mut power = t;
+    let mut n = 0;
+    while n < 20 {
+        let term = power / (2 * n + 1) as f64;
+        sum += if n % 2 == 0 { term } else { -term };
+        power *= t * t;
+        n += 1;
+    }
+    let angle = 8.0 * sum;
+    sign * if invert { PI / 2.0 - angle } else { angle }
+}
+
+/// Principal argument atan2(y, x) in (-π, π]
+fn my_atan2(y: f64, x: f64) -> f64 {
+    if x > 0.0 {
+        my_atan(y / x)
+    } else if x < 0.0 {
+        if y >= 0.0 {
+            my_atan(y / x) + PI
+        } else {
+            my_atan(y / x) - PI
+        }
+    } else if y > 0.0 {
+        PI / 2.0
+    } else if y < 0.0 {
+        -PI / 2.0
+    } else {
+        0.0
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Complex elementary functions on (re, im) pairs, principal branches
+// ---------------------------------------------------------------------------
+
+/// exp(x + iy) = eˣ(cos y + i sin y)
+fn complex_exp(re: f64, im: f64) -> (f64, f64) {
+    let r = my_exp(re);
+    (r * my_cos(im), r * my_sin(im))
+}
+
+/// log z = ln|z| + i·arg z, arg in (-π, π], branch cut along the negative real axis.
+/// ln|z| = ln a + ½ ln(1 + (b/a)²) with a = max(|re|, |im|) and b the other
+/// magnitude, so tiny or huge |z| does not underflow or overflow re² + im².
+/// log 0 = -inf.
+fn complex_log(re: f64, im: f64) -> (f64, f64) {
+    let (abs_re, abs_im) = (
+        if re < 0.0 { -re } else { re },
+        if im < 0.0 { -im } else { im },
+    );
+    let (a, b) = if abs_re >= abs_im {
+        (abs_re, abs_im)
+    } else {
+        (abs_im, abs_re)
+    };
+    if a == 0.0 {
+        return (f64::NEG_INFINITY, my_atan2(im, re));
+    }
+    let ratio = b / a;
+    (
+        my_ln(a) + 0.5 * my_ln(1.0 + ratio * ratio),
+        my_atan2(im, re),
+    )
+}
+
+/// sin(x + iy) = sin x cosh y + i cos x sinh y
+fn complex_sin(re: f64, im: f64) -> (f64, f64) {
+    let (e, inv) = (my_exp(im), my_exp(-im));
+    let (cosh, sinh) = (0.5 * (e + inv), 0.5 * (e - inv));
+    (my_sin(re) * cosh, my_cos(re) * sinh)
+}
+
+/// cos(x + iy) = cos x cosh y - i sin x sinh y
+fn complex_cos(re: f64, im: f64) -> (f64, f64) {
+    let (e, inv) = (my_exp(im), my_exp(-im));
+    let (cosh, sinh) = (0.5 * (e + inv), 0.5 * (e - inv));
+    (my_cos(re) * cosh, -my_sin(re) * sinh)
+}
+
+/// Principal √z with Re ≥ 0: (√((|z| + x)/2), ±√((|z| - x)/2)), sign of y
+fn complex_sqrt(re: f64, im: f64) -> (f64, f64) {
+    let r = my_sqrt(re * re + im * im);
+    let out_re = my_sqrt(0.5 * (r + re));
+    let out_im = my_sqrt(0.5 * (r - re));
+    (out_re, if im < 0.0 { -out_im } else { out_im })
+}
+
+/// Principal power zʷ = exp(w·log z). At z = 0 it returns 0, the limit for
+/// Re w > 0, instead of exp(w·(-inf)), which is NaN whenever w has a zero part.
+fn complex_pow(re: f64, im: f64, w_re: f64, w_im: f64) -> (f64, f64) {
+    if re == 0.0 && im == 0.0 {
+        return (0.0, 0.0);
+    }
+    let (l_re, l_im) = complex_log(re, im);
+    let (e_re, e_im) = complex_mul(w_re, w_im, l_re, l_im);
+    complex_exp(e_re, e_im)
+}
+
 /// Complex squared magnitude: |z|² = re² + im²
 /// ∂|z|²/∂re = 2*re, ∂|z|²/∂im = 2*im
 #[autodiff_reverse(d_complex_mag_sq, Active, Active, Active)]
@@ -31,26 +291,179 @@ fn mandelbrot_step(z_re: f64, z_im: f64, c_re: f64, c_im: f64) -> f64 {
     new_re * new_re + new_im * new_im
 }
 
-/// Complex exponential approximation: exp(z) = exp(re)(cos(im) + i*sin(im))
-/// Returns |exp(z)|² = exp(2*re)
+/// |exp(z)|² through the full complex exponential, so the imaginary part
+/// is exercised: |eˣ(cos y + i sin y)|² = e²ˣ and ∂/∂y = 0 by cancellation
 #[autodiff_reverse(d_complex_exp_mag, Active, Active, Active)]
-fn complex_exp_mag_squared(re: f64, _im: f64) -> f64 {
-    // |exp(z)|² = |exp(re)|² * |cos(im) + i*sin(im)|²
-    //           = exp(2*re) * 1 = exp(2*re)
-    let mut exp_2re = 1.0;
-    let x = 2.0 * re;
-    let mut term = 1.0;
-    let mut k = 1;
-    while k < 50 {
-        term *= x / (k as f64);
-        exp_2re += term;
-        k += 1;
+fn complex_exp_mag_squared(re: f64, im: f64) -> f64 {
+    let (e_re, e_im) = complex_exp(re, im);
+    e_re * e_re + e_im * e_im
+}
+
+/// |exp(z)·sin(z)|² = e²ˣ(sin²x + sinh²y), which depends on both parts
+#[autodiff_reverse(d_exp_sin_mag, Active, Active, Active)]
+fn exp_sin_mag_squared(re: f64, im: f64) -> f64 {
+    let (e_re, e_im) = complex_exp(re, im);
+    let (s_re, s_im) = complex_sin(re, im);
+    let (p_re, p_im) = complex_mul(e_re, e_im, s_re, s_im);
+    p_re * p_re + p_im * p_im
+}
+
+/// Re(z²) = re² - im²
+#[autodiff_reverse(d_re_z_squared, Active, Active, Active)]
+fn re_z_squared(re: f64, im: f64) -> f64 {
+    complex_mul(re, im, re, im).0
+}
+
+/// Wirtinger derivatives of a real-valued f at one complex input z = x + iy:
+///
+/// ∂f/∂z = (∂f/∂x - i ∂f/∂y) / 2,  ∂f/∂z̄ = (∂f/∂x + i ∂f/∂y) / 2
+///
+/// Since f is real, ∂f/∂z̄ = conj(∂f/∂z), and the steepest-ascent direction
+/// in the complex plane is 2·∂f/∂z̄ = ∂f/∂x + i ∂f/∂y.
+#[derive(Debug, Clone, Copy)]
+struct Wirtinger {
+    /// ∂f/∂z as (re, im)
+    dz: (f64, f64),
+    /// ∂f/∂z̄ as (re, im)
+    dz_bar: (f64, f64),
+}
+
+impl Wirtinger {
+    /// Assembles both derivatives from the real gradient (∂f/∂x, ∂f/∂y)
+    fn from_gradient(d_re: f64, d_im: f64) -> Self {
+        Wirtinger {
+            dz: (0.5 * d_re, -0.5 * d_im),
+            dz_bar: (0.5 * d_re, 0.5 * d_im),
+        }
+    }
+}
+
+/// Value and Wirtinger derivatives of f(z) from its reverse-mode gradient
+/// function, for the `(re, im) -> f64` signature with `Active` arguments
+fn wirtinger(d_f: fn(f64, f64, f64) -> (f64, f64, f64), re: f64, im: f64) -> (f64, Wirtinger) {
+    let (value, d_re, d_im) = d_f(re, im, 1.0);
+    (value, Wirtinger::from_gradient(d_re, d_im))
+}
+
+/// Fixed multiplier for `scale_by_a`
+const A: (f64, f64) = (0.6, -1.2);
+
+/// f(z) = A·z, holomorphic with f'(z) = A
+#[autodiff_forward(d_scale_by_a, Dual, Dual, Dual)]
+fn scale_by_a(re: f64, im: f64) -> (f64, f64) {
+    complex_mul(A.0, A.1, re, im)
+}
+
+/// f(z) = z², holomorphic with f'(z) = 2z
+#[autodiff_forward(d_square, Dual, Dual, Dual)]
+fn square(re: f64, im: f64) -> (f64, f64) {
+    complex_mul(re, im, re, im)
+}
+
+/// f(z) = z̄, not holomorphic: ∂f/∂z̄ = 1
+#[autodiff_forward(d_conjugate, Dual, Dual, Dual)]
+fn conjugate(re: f64, im: f64) -> (f64, f64) {
+    (re, -im)
+}
+
+/// f(z) = |z|² as a complex map, not holomorphic: ∂f/∂z̄ = z
+#[autodiff_forward(d_mag_sq_map, Dual, Dual, Dual)]
+fn mag_sq_map(re: f64, im: f64) -> (f64, f64) {
+    (re * re + im * im, 0.0)
+}
+
+/// Fixed exponent for `pow_map`
+const W: (f64, f64) = (0.5, 0.25);
+
+#[autodiff_forward(d_exp_map, Dual, Dual, Dual)]
+fn exp_map(re: f64, im: f64) -> (f64, f64) {
+    complex_exp(re, im)
+}
+
+#[autodiff_forward(d_log_map, Dual, Dual, Dual)]
+fn log_map(re: f64, im: f64) -> (f64, f64) {
+    complex_log(re, im)
+}
+
+#[autodiff_forward(d_sin_map, Dual, Dual, Dual)]
+fn sin_map(re: f64, im: f64) -> (f64, f64) {
+    complex_sin(re, im)
+}
+
+#[autodiff_forward(d_cos_map, Dual, Dual, Dual)]
+fn cos_map(re: f64, im: f64) -> (f64, f64) {
+    complex_cos(re, im)
+}
+
+#[autodiff_forward(d_sqrt_map, Dual, Dual, Dual)]
+fn sqrt_map(re: f64, im: f64) -> (f64, f64) {
+    complex_sqrt(re, im)
+}
+
+#[autodiff_forward(d_pow_map, Dual, Dual, Dual)]
+fn pow_map(re: f64, im: f64) -> (f64, f64) {
+    complex_pow(re, im, W.0, W.1)
+}
+
+/// Forward-mode derivative of a complex map f(x + iy) = u + iv:
+/// (re, d_re, im, d_im) -> ((u, v), (du, dv))
+type ComplexTangent = fn(f64, f64, f64, f64) -> ((f64, f64), (f64, f64));
+
+/// Real Jacobian [[∂u/∂x, ∂u/∂y], [∂v/∂x, ∂v/∂y]], one forward pass per column
+fn real_jacobian(d_f: ComplexTangent, re: f64, im: f64) -> [[f64; 2]; 2] {
+    let (_, (ux, vx)) = d_f(re, 1.0, im, 0.0);
+    let (_, (uy, vy)) = d_f(re, 0.0, im, 1.0);
+    [[ux, uy], [vx, vy]]
+}
+
+/// Why a map is not complex-differentiable at a point
+#[derive(Debug, Clone, Copy)]
+struct NotHolomorphic {
+    /// ∂f/∂z, the holomorphic part
+    dz: (f64, f64),
+    /// ∂f/∂z̄, the part that violates Cauchy-Riemann
+    dz_bar: (f64, f64),
+}
+
+/// f'(z) if the Cauchy-Riemann equations u_x = v_y, u_y = -v_x hold to `tol`
+///
+/// In terms of the Jacobian, ∂f/∂z = ((u_x + v_y) + i(v_x - u_y)) / 2 and
+/// ∂f/∂z̄ = ((u_x - v_y) + i(v_x + u_y)) / 2. f is holomorphic when ∂f/∂z̄ = 0,
+/// and then f'(z) = ∂f/∂z.
+fn complex_derivative(
+    d_f: ComplexTangent,
+    re: f64,
+    im: f64,
+    tol: f64,
+) -> Result<(f64, f64), NotHolomorphic> {
+    let [[ux, uy], [vx, vy]] = real_jacobian(d_f, re, im);
+    let dz = (0.5 * (ux + vy), 0.5 * (vx - uy));
+    let dz_bar = (0.5 * (ux - vy), 0.5 * (vx + uy));
+    if dz_bar.0.abs().max(dz_bar.1.abs()) <= tol {
+        Ok(dz)
+    } else {
+        Err(NotHolomorphic { dz, dz_bar })
     }
-    exp_2re
 }
 
-/// Wirtinger derivative helper: for f(z, z*) = |z|²
-/// The gradient w.r.t. (re, im) relates to Wirtinger derivatives
+/// Complex division a / b = a·conj(b) / |b|²
+fn complex_div(a: (f64, f64), b: (f64, f64)) -> (f64, f64) {
+    let d = b.0 * b.0 + b.1 * b.1;
+    let (n_re, n_im) = complex_mul(a.0, a.1, b.0, -b.1);
+    (n_re / d, n_im / d)
+}
+
+/// Formats (re, im) as "re ± |im|i"
+fn show(z: (f64, f64)) -> String {
+    let sign = if z.1 < 0.0 { '-' } else { '+' };
+    format!("{:.4} {} {:.4}i", z.0, sign, z.1.abs())
+}
+
+/// Largest component-wise difference between two complex numbers
+fn complex_diff(a: (f64, f64), b: (f64, f64)) -> f64 {
+    (a.0 - b.0).abs().max((a.1 - b.1).abs())
+}
+
 fn main() {
     println!("Complex Number Differentiation");
     println!("==============================\n");
@@ -88,7 +501,7 @@ fn main() {
     // Test 3: Complex exponential magnitude
     println!("3. |exp(z)|² = exp(2·re)");
     let re = 1.0;
-    let im = 2.0; // im doesn't affect magnitude
+    let im = 2.0; // im cancels: |cos + i sin|² = 1
 
     let (exp_mag, d_re, d_im) = d_complex_exp_mag(re, im, 1.0);
     let expected = (2.0 * re).exp();
@@ -101,4 +514,259 @@ fn main() {
         d_re, expected_d_re
     );
     println!("   ∂|exp(z)|²/∂im = {:.6} (expected: 0)", d_im);
+    println!();
+
+    // Test 4: Wirtinger derivatives
+    println!("4. Wirtinger derivatives ∂f/∂z, ∂f/∂z̄ at z = 0.5 + 0.5i, c = -0.5 + 0.5i");
+    let z = (0.5, 0.5);
+    let z_bar = (z.0, -z.1);
+    let c = (-0.5, 0.5);
+    let mut wirtinger_err: f64 = 0.0;
+
+    // |z|² = z z̄: ∂/∂z = z̄, ∂/∂z̄ = z
+    let (_, w) = wirtinger(d_complex_mag_sq, z.0, z.1);
+    println!(
+        "   |z|²:       ∂f/∂z = {}, ∂f/∂z̄ = {}",
+        show(w.dz),
+        show(w.dz_bar)
+    );
+    wirtinger_err = wirtinger_err
+        .max(complex_diff(w.dz, z_bar))
+        .max(complex_diff(w.dz_bar, z));
+
+    // Re(z²) = (z² + z̄²)/2: ∂/∂z = z, ∂/∂z̄ = z̄
+    let (_, w) = wirtinger(d_re_z_squared, z.0, z.1);
+    println!(
+        "   Re(z²):     ∂f/∂z = {}, ∂f/∂z̄ = {}",
+        show(w.dz),
+        show(w.dz_bar)
+    );
+    wirtinger_err = wirtinger_err
+        .max(complex_diff(w.dz, z))
+        .max(complex_diff(w.dz_bar, z_bar));
+
+    // |w|² with w = z² + c: ∂/∂z = 2z·w̄, ∂/∂c = w̄
+    let (_, dz_re, dz_im, dc_re, dc_im) = d_mandelbrot_step(z.0, z.1, c.0, c.1, 1.0);
+    let (wz, wc) = (
+        Wirtinger::from_gradient(dz_re, dz_im),
+        Wirtinger::from_gradient(dc_re, dc_im),
+    );
+    let (sq_re, sq_im) = complex_mul(z.0, z.1, z.0, z.1);
+    let w_bar = (sq_re + c.0, -(sq_im + c.1));
+    let (zw_re, zw_im) = complex_mul(z.0, z.1, w_bar.0, w_bar.1);
+    println!(
+        "   |z² + c|²:  ∂f/∂z = {}, ∂f/∂c = {}",
+        show(wz.dz),
+        show(wc.dz)
+    );
+    wirtinger_err = wirtinger_err
+        .max(complex_diff(wz.dz, (2.0 * zw_re, 2.0 * zw_im)))
+        .max(complex_diff(wc.dz, w_bar))
+        .max(complex_diff(wz.dz_bar, (wz.dz.0, -wz.dz.1)));
+
+    println!();
+
+    // Test 5: Holomorphic derivatives via the real Jacobian
+    println!("5. Complex derivative f'(z) via forward mode at z = 0.5 + 0.5i");
+    let tol = 1e-9;
+    let two_z = (2.0 * z.0, 2.0 * z.1);
+    let cases: [(&str, ComplexTangent, _); 4] = [
+        ("A·z", d_scale_by_a, Ok(A)),
+        ("z²", d_square, Ok(two_z)),
+        (
+            "conj(z)",
+            d_conjugate,
+            Err(NotHolomorphic {
+                dz: (0.0, 0.0),
+                dz_bar: (1.0, 0.0),
+            }),
+        ),
+        (
+            "|z|²",
+            d_mag_sq_map,
+            Err(NotHolomorphic {
+                dz: z_bar,
+                dz_bar: z,
+            }),
+        ),
+    ];
+    let mut holomorphic_ok = true;
+    for (name, d_f, expected) in cases {
+        let result = complex_derivative(d_f, z.0, z.1, tol);
+        let matches = match (&result, &expected) {
+            (Ok(d), Ok(e)) => complex_diff(*d, *e) < tol,
+            (Err(d), Err(e)) => {
+                complex_diff(d.dz, e.dz) < tol && complex_diff(d.dz_bar, e.dz_bar) < tol
+            }
+            _ => false,
+        };
+        holomorphic_ok &= matches;
+        match result {
+            Ok(d) => println!("   {:<7} holomorphic, f'(z) = {}", name, show(d)),
+            Err(e) => println!(
+                "   {:<7} not holomorphic: ∂f/∂z = {}, ∂f/∂z̄ = {}",
+                name,
+                show(e.dz),
+                show(e.dz_bar)
+            ),
+        }
+    }
+
+    println!();
+
+    // Test 6: Complex elementary functions
+    println!("6. Elementary functions at z = -1.3 + 0.7i (principal branches)");
+    let z = (-1.3, 0.7);
+    let std_exp = |(x, y): (f64, f64)| (x.exp() * y.cos(), x.exp() * y.sin());
+    let std_log = |(x, y): (f64, f64)| (x.hypot(y).ln(), y.atan2(x));
+    let inv_z = complex_div((1.0, 0.0), z);
+    let sqrt_z = {
+        let r = z.0.hypot(z.1);
+        (
+            ((r + z.0) / 2.0).sqrt(),
+            z.1.signum() * ((r - z.0) / 2.0).sqrt(),
+        )
+    };
+    let (log_re, log_im) = std_log(z);
+    let pow_z = std_exp(complex_mul(W.0, W.1, log_re, log_im));
+    let sin_z = (z.0.sin() * z.1.cosh(), z.0.cos() * z.1.sinh());
+    let cos_z = (z.0.cos() * z.1.cosh(), -z.0.sin() * z.1.sinh());
+    // (name, value, std reference, forward-mode map, exact f'(z))
+    let functions: [(&str, _, _, ComplexTangent, _); 6] = [
+        (
+            "exp",
+            complex_exp(z.0, z.1),
+            std_exp(z),
+            d_exp_map,
+            std_exp(z),
+        ),
+        (
+            "log",
+            complex_log(z.0, z.1),
+            (log_re, log_im),
+            d_log_map,
+            inv_z,
+        ),
+        ("sin", complex_sin(z.0, z.1), sin_z, d_sin_map, cos_z),
+        (
+            "cos",
+            complex_cos(z.0, z.1),
+            cos_z,
+            d_cos_map,
+            (-sin_z.0, -sin_z.1),
+        ),
+        (
+            "sqrt",
+            complex_sqrt(z.0, z.1),
+            sqrt_z,
+            d_sqrt_map,
+            complex_div((0.5, 0.0), sqrt_z),
+        ),
+        (
+            "pow",
+            complex_pow(z.0, z.1, W.0, W.1),
+            pow_z,
+            d_pow_map,
+            complex_mul(W.0, W.1, complex_div(pow_z, z).0, complex_div(pow_z, z).1),
+        ),
+    ];
+    println!(
+        "   {:<5} {:>24} {:>24} {:>10}",
+        "f", "f(z)", "f'(z)", "error"
+    );
+    let mut elementary_err: f64 = 0.0;
+    for (name, value, reference, d_f, exact) in functions {
+        let derivative_err = match complex_derivative(d_f, z.0, z.1, 1e-9) {
+            Ok(d) => complex_diff(d, exact),
+            Err(_) => f64::INFINITY,
+        };
+        let err = complex_diff(value, reference).max(derivative_err);
+        elementary_err = elementary_err.max(err);
+        println!(
+            "   {:<5} {:>24} {:>24} {:>10.1e}",
+            name,
+            show(value),
+            show(exact),
+            err
+        );
+    }
+    // Branch points of the principal branches on the negative real axis
+    let branch_err = complex_diff(complex_log(-1.0, 0.0), (0.0, PI))
+        .max(complex_diff(complex_sqrt(-4.0, 0.0), (0.0, 2.0)))
+        .max(complex_diff(complex_sqrt(-4.0, -1e-300), (0.0, -2.0)));
+    println!(
+        "   log(-1) = {}, √(-4) = {}",
+        show(complex_log(-1.0, 0.0)),
+        show(complex_sqrt(-4.0, 0.0))
+    );
+
+    // Inputs that used to hang the range-reduction loops
+    let tiny = complex_log(1e-200, 1e-200);
+    let edge_ok = complex_log(0.0, 0.0).0 == f64::NEG_INFINITY
+        && complex_pow(0.0, 0.0, W.0, W.1) == (0.0, 0.0)
+        && (tiny.0 - (1e-200_f64.ln() + 0.5 * 2.0_f64.ln())).abs() < 1e-12
+        && [1e16, 1e18, 1e20, -1e18, 1e300].iter().all(|&x| {
+            reduce_angle(x).abs() <= PI && my_sin(x).abs() <= 1.0 && my_cos(x).abs() <= 1.0
+        })
+        && my_cos(f64::INFINITY).is_nan()
+        && my_ln(-1.0).is_nan()
+        && my_ln(f64::INFINITY) == f64::INFINITY;
+    println!(
+        "   log(0) = {}, log(1e-200 + 1e-200i) = {}",
+        show(complex_log(0.0, 0.0)),
+        show(tiny)
+    );
+    println!(
+        "   sin(1e16) = {:.6}, sin(1e18) = {:.6}, sin(1e20) = {:.6}",
+        my_sin(1e16),
+        my_sin(1e18),
+        my_sin(1e20)
+    );
+
+    println!("\n   |exp(z)·sin(z)|² = e²ˣ(sin²x + sinh²y) at z = 0.4 + 0.9i");
+    let (x, y) = (0.4_f64, 0.9_f64);
+    let (f, d_re, d_im) = d_exp_sin_mag(x, y, 1.0);
+    let e2x = (2.0 * x).exp();
+    let exact_f = e2x * (x.sin().powi(2) + y.sinh().powi(2));
+    let exact_d_re = 2.0 * exact_f + e2x * (2.0 * x).sin();
+    let exact_d_im = e2x * (2.0 * y).sinh();
+    println!("   f = {:.10} (expected: {:.10})", f, exact_f);
+    println!("   ∂f/∂re = {:.10} (expected: {:.10})", d_re, exact_d_re);
+    println!("   ∂f/∂im = {:.10} (expected: {:.10})", d_im, exact_d_im);
+    let exp_sin_err = (f - exact_f)
+        .abs()
+        .max((d_re - exact_d_re).abs())
+        .max((d_im - exact_d_im).abs());
+
+    println!("\n--- Verification ---");
+    if wirtinger_err < 1e-12 {
+        println!("PASS: Wirtinger derivatives match z̄, z, 2z·w̄ and w̄");
+    } else {
+        println!("FAIL: Wirtinger error {:.2e}", wirtinger_err);
+    }
+    if holomorphic_ok {
+        println!("PASS: Cauchy-Riemann check gives A and 2z, and flags z̄ and |z|²");
+    } else {
+        println!("FAIL: complex derivative mismatch");
+    }
+    if elementary_err < 1e-9 && branch_err < 1e-12 {
+        println!(
+            "PASS: exp, log, sin, cos, sqrt, pow match std values, f'(z) and principal branches"
+        );
+    } else {
+        println!(
+            "FAIL: elementary function error {:.2e}, branch error {:.2e}",
+            elementary_err, branch_err
+        );
+    }
+    if edge_ok {
+        println!("PASS: log at 0 and tiny |z|, pow at 0, huge and non-finite angles stay in range");
+    } else {
+        println!("FAIL: edge-case inputs of log, pow or sin");
+    }
+    if exp_sin_err < 1e-9 {
+        println!("PASS: gradient of |exp(z)·sin(z)|² matches in both real and imaginary parts");
+    } else {
+        println!("FAIL: |exp(z)·sin(z)|² gradient error {:.2e}", exp_sin_err);
+    }
 }
@@ -4,8 +4,14 @@
 //! - State evolution under parameterized Hamiltonian
 //! - Fidelity optimization with GRAPE-like algorithm
 //! - Gradient-based pulse optimization
+//! - Cross-check against the analytic GRAPE gradient
+//! - Trajectory export (Bloch vector, populations, control) as CSV and SVG
 //!
 //! Model: 2-level quantum system (qubit) with drift and control
+//!
+//! Example 26 rewrites the same evolution with `Complex`, `CVec` and `CMat`
+//! types (see "Why Examples 13 and 14 Keep Tuples" in the complex types
+//! chapter).
 
 #![feature(autodiff)]
 use std::autodiff::autodiff_reverse;
@@ -58,9 +64,9 @@ const OMEGA0: f64 = 1.0; // Drift frequency
 
 /// Exact time evolution: U = exp(-i(ω₀σz/2 + Ωσx/2)Δt)
 /// Uses: e^{-i(n·σ)θ/2} = cos(θ/2)I - i sin(θ/2)(n·σ)
-fn apply_exact_step(state: &mut [f64; 4], omega: f64) {
+fn apply_exact_step(state: &mut [f64; 4], omega: f64, dt: f64) {
     let omega_eff = my_sqrt(OMEGA0 * OMEGA0 + omega * omega);
-    let theta = omega_eff * DT;
+    let theta = omega_eff * dt;
 
     // Normalized rotation axis: n = (omega, 0, omega0) / omega_eff
     let nx = omega / omega_eff;
@@ -83,12 +89,18 @@ fn apply_exact_step(state: &mut [f64; 4], omega: f64) {
 /// Quantum gate fidelity: F = |⟨ψ_target|ψ_final⟩|²
 #[autodiff_reverse(d_infidelity, Duplicated, Active)]
 fn infidelity(controls: &[f64; N_STEPS]) -> f64 {
+    infidelity_steps(controls, DT)
+}
+
+/// Infidelity for any number of steps of length dt (one per control)
+#[autodiff_reverse(d_infidelity_steps, Duplicated, Const, Active)]
+fn infidelity_steps(controls: &[f64], dt: f64) -> f64 {
     let mut state = [1.0, 0.0, 0.0, 0.0]; // |0⟩
     let target = [0.0, 0.0, 1.0, 0.0]; // |1⟩ (X gate target)
 
     let mut i = 0;
-    while i < N_STEPS {
-        apply_exact_step(&mut state, controls[i]);
+    while i < controls.len() {
+        apply_exact_step(&mut state, controls[i], dt);
         i += 1;
     }
 
@@ -112,6 +124,285 @@ fn energy_cost(controls: &[f64; N_STEPS]) -> f64 {
     sum
 }
 
+// ---------------------------------------------------------------------------
+// Analytic GRAPE gradient (plain Rust, independent of Enzyme)
+// ---------------------------------------------------------------------------
+
+/// ψ ← (c·I - i(a·σx + b·σz))ψ
+///
+/// The step U, its adjoint U† and its derivative dU/dΩ all have this form.
+fn apply_su2(state: &mut [f64; 4], c: f64, a: f64, b: f64) {
+    let (re0, im0, re1, im1) = (state[0], state[1], state[2], state[3]);
+    state[0] = c * re0 + b * im0 + a * im1;
+    state[1] = c * im0 - b * re0 - a * re1;
+    state[2] = c * re1 - b * im1 + a * im0;
+    state[3] = c * im1 + b * re1 - a * re0;
+}
+
+/// Coefficients (c, a, b) of U = cos(θ/2)I - i sin(θ/2)(nₓσx + n_zσz)
+/// and of its exact derivative dU/dΩ
+fn step_and_derivative(omega: f64, dt: f64) -> ([f64; 3], [f64; 3]) {
+    let omega_eff = (OMEGA0 * OMEGA0 + omega * omega).sqrt();
+    let half = omega_eff * dt / 2.0;
+    let (c, s) = (half.cos(), half.sin());
+    let (nx, nz) = (omega / omega_eff, OMEGA0 / omega_eff);
+
+    // dθ/dΩ = Δt·Ω/ω_eff, dnₓ/dΩ = ω₀²/ω_eff³, dn_z/dΩ = -ω₀Ω/ω_eff³
+    let dhalf = dt * omega / (2.0 * omega_eff);
+    let (dc, ds) = (-s * dhalf, c * dhalf);
+    let omega_eff3 = omega_eff * omega_eff * omega_eff;
+    let dnx = OMEGA0 * OMEGA0 / omega_eff3;
+    let dnz = -OMEGA0 * omega / omega_eff3;
+    (
+        [c, s * nx, s * nz],
+        [dc, ds * nx + s * dnx, ds * nz + s * dnz],
+    )
+}
+
+/// GRAPE: forward states ψₖ = Uₖ⋯U₁|0⟩, backward co-states χₖ = Uₖ₊₁†⋯U_N†|1⟩,
+/// and ∂⟨1|U|0⟩/∂Ωₖ = ⟨χₖ|∂Uₖ/∂Ωₖ|ψₖ₋₁⟩. Returns the infidelity.
+fn grape_gradient(controls: &[f64], dt: f64, grad: &mut [f64]) -> f64 {
+    let n = controls.len();
+    let steps: Vec<([f64; 3], [f64; 3])> = controls
+        .iter()
+        .map(|&omega| step_and_derivative(omega, dt))
+        .collect();
+
+    // Forward pass, keeping ψ₀ … ψ_N
+    let mut forward = vec![[1.0, 0.0, 0.0, 0.0]; n + 1];
+    for k in 0..n {
+        let mut psi = forward[k];
+        let [c, a, b] = steps[k].0;
+        apply_su2(&mut psi, c, a, b);
+        forward[k + 1] = psi;
+    }
+
+    // Overlap o = ⟨1|ψ_N⟩
+    let (o_re, o_im) = (forward[n][2], forward[n][3]);
+
+    // Backward pass: χ starts at |1⟩ and picks up Uₖ† = c·I + i(a·σx + b·σz)
+    let mut chi = [0.0, 0.0, 1.0, 0.0];
+    for k in (0..n).rev() {
+        let mut d_psi = forward[k];
+        let [dc, da, db] = steps[k].1;
+        apply_su2(&mut d_psi, dc, da, db);
+
+        // ⟨χ|dψ⟩
+        let ov_re = chi[0] * d_psi[0] + chi[1] * d_psi[1] + chi[2] * d_psi[2] + chi[3] * d_psi[3];
+        let ov_im = chi[0] * d_psi[1] - chi[1] * d_psi[0] + chi[2] * d_psi[3] - chi[3] * d_psi[2];
+
+        // J = 1 - |o|²  ⇒  ∂J/∂Ωₖ = -2 Re(conj(o)·∂o/∂Ωₖ)
+        grad[k] = -2.0 * (o_re * ov_re + o_im * ov_im);
+
+        let [c, a, b] = steps[k].0;
+        apply_su2(&mut chi, c, -a, -b);
+    }
+
+    1.0 - (o_re * o_re + o_im * o_im)
+}
+
+/// Average wall time of `f` over enough repetitions to fill ~50 ms
+fn time_per_call(mut f: impl FnMut()) -> f64 {
+    let mut reps = 1;
+    loop {
+        let start = std::time::Instant::now();
+        for _ in 0..reps {
+            f();
+        }
+        let elapsed = start.elapsed().as_secs_f64();
+        if elapsed > 0.05 {
+            return elapsed / reps as f64;
+        }
+        reps *= 2;
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Trajectory export (plain Rust, not differentiated)
+// ---------------------------------------------------------------------------
+
+/// Output directory for the CSV and SVG files, relative to the working directory
+const OUTPUT_DIR: &str = "target/quantum_control";
+
+/// States ψ₀ … ψ_N after every exact step
+fn record_trajectory(controls: &[f64], dt: f64) -> Vec<[f64; 4]> {
+    let mut states = vec![[1.0, 0.0, 0.0, 0.0]];
+    let mut state = states[0];
+    for &omega in controls {
+        apply_exact_step(&mut state, omega, dt);
+        states.push(state);
+    }
+    states
+}
+
+/// Bloch vector (x, y, z) = (2 Re(c̄₀c₁), 2 Im(c̄₀c₁), |c₀|² - |c₁|²)
+fn bloch_vector(state: &[f64; 4]) -> [f64; 3] {
+    let (re0, im0, re1, im1) = (state[0], state[1], state[2], state[3]);
+    [
+        2.0 * (re0 * re1 + im0 * im1),
+        2.0 * (re0 * im1 - im0 * re1),
+        re0 * re0 + im0 * im0 - re1 * re1 - im1 * im1,
+    ]
+}
+
+/// Populations (|c₀|², |c₁|²)
+fn populations(state: &[f64; 4]) -> [f64; 2] {
+    [
+        state[0] * state[0] + state[1] * state[1],
+        state[2] * state[2] + state[3] * state[3],
+    ]
+}
+
+/// One curve in an SVG plot
+struct Series<'a> {
+    label: &'a str,
+    color: &'a str,
+    points: Vec<(f64, f64)>,
+}
+
+/// Writes a minimal line chart: axes, min/max tick labels and a legend
+fn write_svg_plot(path: &str, title: &str, series: &[Series]) -> std::io::Result<()> {
+    let (width, height, margin) = (640.0, 360.0, 50.0);
+    let all = series.iter().flat_map(|s| s.points.iter());
+    let (mut x_min, mut x_max) = (f64::INFINITY, f64::NEG_INFINITY);
+    let (mut y_min, mut y_max) = (f64::INFINITY, f64::NEG_INFINITY);
+    for &(x, y) in all {
+        x_min = x_min.min(x);
+        x_max = x_max.max(x);
+        y_min = y_min.min(y);
+        y_max = y_max.max(y);
+    }
+    let pad = 0.05 * (y_max - y_min).max(1e-12);
+    let (y_min, y_max) = (y_min - pad, y_max + pad);
+    let sx = |x: f64| margin + (x - x_min) / (x_max - x_min) * (width - 2.0 * margin);
+    let sy = |y: f64| height - margin - (y - y_min) / (y_max - y_min) * (height - 2.0 * margin);
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" \
+         font-family=\"sans-serif\" font-size=\"12\">\n\
+         <rect width=\"100%\" height=\"100%\" fill=\"white\"/>\n\
+         <text x=\"{}\" y=\"20\" text-anchor=\"middle\" font-size=\"14\">{title}</text>\n\
+         <rect x=\"{margin}\" y=\"{margin}\" width=\"{}\" height=\"{}\" fill=\"none\" stroke=\"black\"/>\n",
+        width / 2.0,
+        width - 2.0 * margin,
+        height - 2.0 * margin
+    );
+    if y_min < 0.0 && y_max > 0.0 {
+        svg += &format!(
+            "<line x1=\"{margin}\" y1=\"{0:.1}\" x2=\"{1}\" y2=\"{0:.1}\" stroke=\"#ccc\"/>\n",
+            sy(0.0),
+            width - margin
+        );
+    }
+    svg += &format!(
+        "<text x=\"{margin}\" y=\"{}\" text-anchor=\"middle\">{x_min:.2}</text>\n\
+         <text x=\"{}\" y=\"{}\" text-anchor=\"middle\">{x_max:.2}</text>\n\
+         <text x=\"{}\" y=\"{}\" text-anchor=\"end\">{y_min:.2}</text>\n\
+         <text x=\"{}\" y=\"{}\" text-anchor=\"end\">{y_max:.2}</text>\n\
+         <text x=\"{}\" y=\"{}\" text-anchor=\"middle\">t</text>\n",
+        height - margin + 16.0,
+        width - margin,
+        height - margin + 16.0,
+        margin - 4.0,
+        height - margin,
+        margin - 4.0,
+        margin + 10.0,
+        width / 2.0,
+        height - margin + 16.0
+    );
+    for (i, s) in series.iter().enumerate() {
+        let points: Vec<String> = s
+            .points
+            .iter()
+            .map(|&(x, y)| format!("{:.1},{:.1}", sx(x), sy(y)))
+            .collect();
+        let legend_y = margin + 16.0 + 16.0 * i as f64;
+        svg += &format!(
+            "<polyline fill=\"none\" stroke=\"{}\" stroke-width=\"1.5\" points=\"{}\"/>\n\
+             <text x=\"{}\" y=\"{legend_y}\" fill=\"{}\" text-anchor=\"end\">{}</text>\n",
+            s.color,
+            points.join(" "),
+            width - margin - 8.0,
+            s.color,
+            s.label
+        );
+    }
+    svg += "</svg>\n";
+    std::fs::write(path, svg)
+}
+
+/// Writes trajectory.csv, control.svg, populations.svg and bloch.svg;
+/// returns the paths written
+fn export_trajectory(
+    controls: &[f64],
+    states: &[[f64; 4]],
+    dt: f64,
+) -> std::io::Result<Vec<String>> {
+    std::fs::create_dir_all(OUTPUT_DIR)?;
+    let times: Vec<f64> = (0..states.len()).map(|k| k as f64 * dt).collect();
+    let pops: Vec<[f64; 2]> = states.iter().map(populations).collect();
+    let bloch: Vec<[f64; 3]> = states.iter().map(bloch_vector).collect();
+
+    // Row k: state after k steps, and the control on the step starting at t_k
+    let mut csv = String::from("t,control,p0,p1,x,y,z\n");
+    for k in 0..states.len() {
+        let omega = controls[k.min(controls.len() - 1)];
+        csv += &format!(
+            "{:.6},{omega:.10},{:.12},{:.12},{:.12},{:.12},{:.12}\n",
+            times[k], pops[k][0], pops[k][1], bloch[k][0], bloch[k][1], bloch[k][2]
+        );
+    }
+    let csv_path = format!("{OUTPUT_DIR}/trajectory.csv");
+    std::fs::write(&csv_path, csv)?;
+
+    // Piecewise-constant control drawn as steps
+    let control_points: Vec<(f64, f64)> = controls
+        .iter()
+        .enumerate()
+        .flat_map(|(k, &omega)| [(times[k], omega), (times[k + 1], omega)])
+        .collect();
+    let curve = |label, color, f: &dyn Fn(usize) -> f64| Series {
+        label,
+        color,
+        points: (0..states.len()).map(|k| (times[k], f(k))).collect(),
+    };
+    let plots = [
+        (
+            "control.svg",
+            "Control Ω(t)",
+            vec![Series {
+                label: "Ω",
+                color: "#1f77b4",
+                points: control_points,
+            }],
+        ),
+        (
+            "populations.svg",
+            "Level populations",
+            vec![
+                curve("|c₀|²", "#1f77b4", &|k| pops[k][0]),
+                curve("|c₁|²", "#d62728", &|k| pops[k][1]),
+            ],
+        ),
+        (
+            "bloch.svg",
+            "Bloch vector",
+            vec![
+                curve("x", "#1f77b4", &|k| bloch[k][0]),
+                curve("y", "#2ca02c", &|k| bloch[k][1]),
+                curve("z", "#d62728", &|k| bloch[k][2]),
+            ],
+        ),
+    ];
+    let mut written = vec![csv_path];
+    for (file, title, series) in plots {
+        let path = format!("{OUTPUT_DIR}/{file}");
+        write_svg_plot(&path, title, &series)?;
+        written.push(path);
+    }
+    Ok(written)
+}
+
 fn main() {
     println!("Quantum Optimal Control with Autodiff");
     println!("======================================\n");
@@ -170,7 +461,99 @@ fn main() {
         pi
     );
 
+    println!("\n--- Trajectory Export ---");
+    let states = record_trajectory(&controls, DT);
+    let norm_err = states
+        .iter()
+        .map(|s| (populations(s)[0] + populations(s)[1] - 1.0).abs())
+        .fold(0.0, f64::max);
+    let [x, y, z] = bloch_vector(&states[N_STEPS]);
+    println!("Final Bloch vector: ({x:.6}, {y:.6}, {z:.6})");
+    for k in [0, N_STEPS / 4, N_STEPS / 2, 3 * N_STEPS / 4, N_STEPS] {
+        let [p0, p1] = populations(&states[k]);
+        println!(
+            "  t = {:.2}: |c₀|² = {p0:.4}, |c₁|² = {p1:.4}",
+            k as f64 * DT
+        );
+    }
+    match export_trajectory(&controls, &states, DT) {
+        Ok(paths) => {
+            for path in paths {
+                println!("Wrote {path}");
+            }
+        }
+        Err(e) => println!("Could not write to {OUTPUT_DIR}: {e}"),
+    }
+
+    println!("\n--- Analytic GRAPE Cross-Check ---");
+    let mut grape_grad = vec![0.0; N_STEPS];
+    let grape_infid = grape_gradient(&controls, DT, &mut grape_grad);
+    let grape_err = final_grad
+        .iter()
+        .zip(&grape_grad)
+        .map(|(a, b)| (a - b).abs())
+        .fold(0.0, f64::max);
+    println!("Infidelity: Enzyme {final_infid:.3e}, GRAPE {grape_infid:.3e}");
+    println!("max |∇_Enzyme - ∇_GRAPE| at the optimum = {grape_err:.2e}");
+
+    // Away from the optimum the gradient is large, so compare there too
+    let mut rng = 1u64;
+    let random: Vec<f64> = (0..N_STEPS)
+        .map(|_| {
+            rng = rng
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            8.0 * ((rng >> 11) as f64 / (1u64 << 53) as f64) - 4.0
+        })
+        .collect();
+    let mut enzyme_grad = vec![0.0; N_STEPS];
+    d_infidelity_steps(&random, &mut enzyme_grad, DT, 1.0);
+    grape_gradient(&random, DT, &mut grape_grad);
+    let random_err = enzyme_grad
+        .iter()
+        .zip(&grape_grad)
+        .map(|(a, b)| (a - b).abs())
+        .fold(0.0, f64::max);
+    println!("max |∇_Enzyme - ∇_GRAPE| at random controls = {random_err:.2e}");
+
+    println!("\nTiming per gradient as N_STEPS grows (T = 1):");
+    println!(
+        "{:>8} {:>14} {:>14} {:>8}",
+        "steps", "Enzyme", "GRAPE", "ratio"
+    );
+    for n in [100, 200, 400, 800, 1600, 3200] {
+        let dt = 1.0 / n as f64;
+        let c: Vec<f64> = (0..n).map(|k| random[k % N_STEPS]).collect();
+        let mut g = vec![0.0; n];
+        let t_enzyme = time_per_call(|| {
+            g.iter_mut().for_each(|x| *x = 0.0);
+            d_infidelity_steps(&c, &mut g, dt, 1.0);
+        });
+        let t_grape = time_per_call(|| {
+            grape_gradient(&c, dt, &mut g);
+        });
+        println!(
+            "{n:>8} {:>11.1} µs {:>11.1} µs {:>8.2}",
+            t_enzyme * 1e6,
+            t_grape * 1e6,
+            t_enzyme / t_grape
+        );
+    }
+
     println!("\n--- Verification ---");
+    if grape_err.max(random_err) < 1e-8 {
+        println!("PASS: Enzyme and analytic GRAPE gradients agree to 1e-8");
+    } else {
+        println!(
+            "FAIL: gradients differ by {:.2e}",
+            grape_err.max(random_err)
+        );
+    }
+    if norm_err < 1e-12 && (z - (1.0 - 2.0 * final_fidelity)).abs() < 1e-12 {
+        println!("PASS: Trajectory stays normalized and ends at z = 1 - 2F");
+    } else {
+        println!("FAIL: normalization error {norm_err:.2e}, final z = {z:.6}");
+    }
     if final_fidelity > 0.9999 {
         println!("SUCCESS: Achieved >99.99% fidelity!");
     } else if final_fidelity > 0.999 {
@@ -0,0 +1,321 @@
+//! Example 15: Recurrent Cells and Backpropagation Through Time
+//!
+//! Unrolls a vanilla RNN cell and an LSTM cell over a whole sequence inside a
+//! single `#[autodiff_reverse]` function. Enzyme differentiates the `while`
+//! loop over time steps, which is exactly backpropagation through time (BPTT).
+//!
+//! Toy task: read a stream of bits and predict the fraction of ones,
+//! i.e. (Σ xₜ) / SEQ_LEN, from the final hidden state.
+//!
+//! Run with: RUSTFLAGS="-Z autodiff=Enable" cargo +enzyme run -p recurrent_cell
+
+#![feature(autodiff)]
+
+use std::autodiff::autodiff_reverse;
+
+const SEQ_LEN: usize = 8;
+const HIDDEN: usize = 6;
+const N_TRAIN: usize = 64;
+const N_ITERS: usize = 1500;
+
+/// exp(x) via argument halving and a Taylor series
+fn my_exp(x: f64) -> f64 {
+    let mut r = x;
+    let mut halvings = 0;
+    while (if r < 0.0 { -r } else { r }) > 0.5 {
+        r /= 2.0;
+        halvings += 1;
+    }
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    let mut k = 1;
+    while k < 20 {
+        term *= r / k as f64;
+        sum += term;
+        k += 1;
+    }
+    let mut i = 0;
+    while i < halvings {
+        sum *= sum;
+        i += 1;
+    }
+    sum
+}
+
+/// Logistic sigmoid σ(x) = 1 / (1 + e⁻ˣ), evaluated without overflow
+fn sigmoid(x: f64) -> f64 {
+    if x >= 0.0 {
+        1.0 / (1.0 + my_exp(-x))
+    } else {
+        let e = my_exp(x);
+        e / (1.0 + e)
+    }
+}
+
+/// tanh(x) = (1 - e⁻²ˣ) / (1 + e⁻²ˣ), evaluated without overflow
+fn my_tanh(x: f64) -> f64 {
+    if x >= 0.0 {
+        let e = my_exp(-2.0 * x);
+        (1.0 - e) / (1.0 + e)
+    } else {
+        let e = my_exp(2.0 * x);
+        (e - 1.0) / (e + 1.0)
+    }
+}
+
+// Parameter layout of the vanilla RNN (one flat slice)
+const RNN_W_XH: usize = 0; // [HIDDEN]
+const RNN_W_HH: usize = RNN_W_XH + HIDDEN; // [HIDDEN × HIDDEN], row-major
+const RNN_B_H: usize = RNN_W_HH + HIDDEN * HIDDEN; // [HIDDEN]
+const RNN_W_HY: usize = RNN_B_H + HIDDEN; // [HIDDEN]
+const RNN_B_Y: usize = RNN_W_HY + HIDDEN; // [1]
+const RNN_N_PARAMS: usize = RNN_B_Y + 1;
+
+// Parameter layout of the LSTM, gates stacked in the order (i, f, g, o)
+const LSTM_W_X: usize = 0; // [4·HIDDEN]
+const LSTM_W_H: usize = LSTM_W_X + 4 * HIDDEN; // [4·HIDDEN × HIDDEN], row-major
+const LSTM_B: usize = LSTM_W_H + 4 * HIDDEN * HIDDEN; // [4·HIDDEN]
+const LSTM_W_HY: usize = LSTM_B + 4 * HIDDEN; // [HIDDEN]
+const LSTM_B_Y: usize = LSTM_W_HY + HIDDEN; // [1]
+const LSTM_N_PARAMS: usize = LSTM_B_Y + 1;
+
+/// Vanilla RNN unrolled over every sequence, with MSE loss on the final output
+///
+/// hₜ = tanh(W_xh xₜ + W_hh hₜ₋₁ + b_h)
+/// y  = W_hy · h_T + b_y
+///
+/// Parameters:
+/// - params: all weights, laid out as RNN_W_XH .. RNN_B_Y (we want gradients)
+/// - seqs: input bits, one sequence of SEQ_LEN after another (constant)
+/// - targets: one target per sequence (constant)
+#[autodiff_reverse(d_rnn_loss, Duplicated, Const, Const, Active)]
+fn rnn_loss(params: &[f64], seqs: &[f64], targets: &[f64]) -> f64 {
+    let n_seq = targets.len();
+    let mut loss = 0.0;
+    let mut s = 0;
+    while s < n_seq {
+        let mut h = [0.0; HIDDEN];
+        let mut t = 0;
+        while t < SEQ_LEN {
+            let x = seqs[s * SEQ_LEN + t];
+            let mut h_new = [0.0; HIDDEN];
+            let mut j = 0;
+            while j < HIDDEN {
+                let mut pre = params[RNN_W_XH + j] * x + params[RNN_B_H + j];
+                let mut k = 0;
+                while k < HIDDEN {
+                    pre += params[RNN_W_HH + j * HIDDEN + k] * h[k];
+                    k += 1;
+                }
+                h_new[j] = my_tanh(pre);
+                j += 1;
+            }
+            h = h_new;
+            t += 1;
+        }
+
+        let mut y = params[RNN_B_Y];
+        let mut j = 0;
+        while j < HIDDEN {
+            y += params[RNN_W_HY + j] * h[j];
+            j += 1;
+        }
+        let diff = y - targets[s];
+        loss += diff * diff;
+        s += 1;
+    }
+    loss / (2.0 * n_seq as f64)
+}
+
+/// LSTM unrolled over every sequence, with MSE loss on the final output
+///
+/// iₜ = σ(·), fₜ = σ(·), gₜ = tanh(·), oₜ = σ(·) with (·) = W_x xₜ + W_h hₜ₋₁ + b
+/// cₜ = fₜ ⊙ cₜ₋₁ + iₜ ⊙ gₜ
+/// hₜ = oₜ ⊙ tanh(cₜ)
+/// y  = W_hy · h_T + b_y
+///
+/// Parameters:
+/// - params: all weights, laid out as LSTM_W_X .. LSTM_B_Y (we want gradients)
+/// - seqs, targets: as in `rnn_loss` (constant)
+#[autodiff_reverse(d_lstm_loss, Duplicated, Const, Const, Active)]
+fn lstm_loss(params: &[f64], seqs: &[f64], targets: &[f64]) -> f64 {
+    let n_seq = targets.len();
+    let mut loss = 0.0;
+    let mut s = 0;
+    while s < n_seq {
+        let mut h = [0.0; HIDDEN];
+        let mut c = [0.0; HIDDEN];
+        let mut t = 0;
+        while t < SEQ_LEN {
+            let x = seqs[s * SEQ_LEN + t];
+
+            // Pre-activations for all four gates
+            let mut z = [0.0; 4 * HIDDEN];
+            let mut r = 0;
+            while r < 4 * HIDDEN {
+                let mut pre = params[LSTM_W_X + r] * x + params[LSTM_B + r];
+                let mut k = 0;
+                while k < HIDDEN {
+                    pre += params[LSTM_W_H + r * HIDDEN + k] * h[k];
+                    k += 1;
+                }
+                z[r] = pre;
+                r += 1;
+            }
+
+            let mut j = 0;
+            while j < HIDDEN {
+                let i_gate = sigmoid(z[j]);
+                let f_gate = sigmoid(z[HIDDEN + j]);
+                let g_gate = my_tanh(z[2 * HIDDEN + j]);
+                let o_gate = sigmoid(z[3 * HIDDEN + j]);
+                c[j] = f_gate * c[j] + i_gate * g_gate;
+                h[j] = o_gate * my_tanh(c[j]);
+                j += 1;
+            }
+            t += 1;
+        }
+
+        let mut y = params[LSTM_B_Y];
+        let mut j = 0;
+        while j < HIDDEN {
+            y += params[LSTM_W_HY + j] * h[j];
+            j += 1;
+        }
+        let diff = y - targets[s];
+        loss += diff * diff;
+        s += 1;
+    }
+    loss / (2.0 * n_seq as f64)
+}
+
+/// Deterministic pseudo-random numbers in [0, 1) (64-bit LCG)
+fn next_uniform(state: &mut u64) -> f64 {
+    *state = state
+        .wrapping_mul(6364136223846793005)
+        .wrapping_add(1442695040888963407);
+    (*state >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Random bit sequences and their targets (fraction of ones)
+fn make_bit_streams(n_seq: usize, rng: &mut u64) -> (Vec<f64>, Vec<f64>) {
+    let mut seqs = Vec::with_capacity(n_seq * SEQ_LEN);
+    let mut targets = Vec::with_capacity(n_seq);
+    for _ in 0..n_seq {
+        let mut ones = 0.0;
+        for _ in 0..SEQ_LEN {
+            let bit = if next_uniform(rng) < 0.5 { 0.0 } else { 1.0 };
+            ones += bit;
+            seqs.push(bit);
+        }
+        targets.push(ones / SEQ_LEN as f64);
+    }
+    (seqs, targets)
+}
+
+fn init_weights(n: usize, scale: f64, rng: &mut u64) -> Vec<f64> {
+    (0..n)
+        .map(|_| scale * (2.0 * next_uniform(rng) - 1.0))
+        .collect()
+}
+
+/// One Adam update; `t` is the 1-based step count
+fn adam_step(params: &mut [f64], grad: &[f64], m: &mut [f64], v: &mut [f64], t: i32, lr: f64) {
+    let (beta1, beta2, epsilon) = (0.9, 0.999, 1e-8);
+    for i in 0..params.len() {
+        m[i] = beta1 * m[i] + (1.0 - beta1) * grad[i];
+        v[i] = beta2 * v[i] + (1.0 - beta2) * grad[i] * grad[i];
+        let m_hat = m[i] / (1.0 - f64::powi(beta1, t));
+        let v_hat = v[i] / (1.0 - f64::powi(beta2, t));
+        params[i] -= lr * m_hat / (v_hat.sqrt() + epsilon);
+    }
+}
+
+/// Largest |autodiff - central difference| over all parameters
+fn max_fd_error(loss: &dyn Fn(&[f64]) -> f64, params: &[f64], grad: &[f64]) -> f64 {
+    let h = 1e-6;
+    let mut worst: f64 = 0.0;
+    for i in 0..params.len() {
+        let mut plus = params.to_vec();
+        let mut minus = params.to_vec();
+        plus[i] += h;
+        minus[i] -= h;
+        let fd = (loss(&plus) - loss(&minus)) / (2.0 * h);
+        worst = worst.max((fd - grad[i]).abs());
+    }
+    worst
+}
+
+/// Signature shared by `d_rnn_loss` and `d_lstm_loss`
+type LossGrad = fn(&[f64], &mut [f64], &[f64], &[f64], f64) -> f64;
+
+/// Adam on a flat parameter vector, with the gradient supplied by `d_loss`
+fn train(name: &str, params: &mut [f64], d_loss: LossGrad, seqs: &[f64], targets: &[f64]) {
+    let lr = 0.01;
+    let mut m = vec![0.0; params.len()];
+    let mut v = vec![0.0; params.len()];
+    for iter in 0..N_ITERS {
+        let mut grad = vec![0.0; params.len()];
+        let loss = d_loss(params, &mut grad, seqs, targets, 1.0);
+        adam_step(params, &grad, &mut m, &mut v, iter as i32 + 1, lr);
+        if iter % 300 == 0 || iter == N_ITERS - 1 {
+            println!("   {name} iter {iter:4}: loss = {loss:.6}");
+        }
+    }
+}
+
+fn main() {
+    println!("Recurrent Cells with Backpropagation Through Time");
+    println!("=================================================\n");
+    println!("Task: predict the fraction of ones in a stream of {SEQ_LEN} bits\n");
+
+    let mut rng = 42;
+    let (seqs, targets) = make_bit_streams(N_TRAIN, &mut rng);
+    let (test_seqs, test_targets) = make_bit_streams(16, &mut rng);
+
+    let mut rnn = init_weights(RNN_N_PARAMS, 0.3, &mut rng);
+
+    // Forget-gate bias starts at 1 so the LSTM remembers by default
+    let mut lstm = init_weights(LSTM_N_PARAMS, 0.3, &mut rng);
+    for j in 0..HIDDEN {
+        lstm[LSTM_B + HIDDEN + j] = 1.0;
+    }
+
+    // 1. Gradient check: BPTT vs central finite differences
+    println!("1. Gradient check (autodiff vs finite differences)");
+    let mut grad = vec![0.0; RNN_N_PARAMS];
+    let _ = d_rnn_loss(&rnn, &mut grad, &seqs, &targets, 1.0);
+    let rnn_err = max_fd_error(&|p| rnn_loss(p, &seqs, &targets), &rnn, &grad);
+    println!("   RNN  ({RNN_N_PARAMS:3} params) max |∇ - FD| = {rnn_err:.2e}");
+
+    let mut grad = vec![0.0; LSTM_N_PARAMS];
+    let _ = d_lstm_loss(&lstm, &mut grad, &seqs, &targets, 1.0);
+    let lstm_err = max_fd_error(&|p| lstm_loss(p, &seqs, &targets), &lstm, &grad);
+    println!("   LSTM ({LSTM_N_PARAMS:3} params) max |∇ - FD| = {lstm_err:.2e}");
+    println!();
+
+    // 2. Training with Adam
+    println!("2. Training with Adam ({N_ITERS} iterations, {N_TRAIN} sequences)");
+    train("RNN ", &mut rnn, d_rnn_loss, &seqs, &targets);
+    train("LSTM", &mut lstm, d_lstm_loss, &seqs, &targets);
+    println!();
+
+    // 3. Held-out sequences
+    println!("3. Held-out sequences");
+    let rnn_test = rnn_loss(&rnn, &test_seqs, &test_targets);
+    let lstm_test = lstm_loss(&lstm, &test_seqs, &test_targets);
+    println!("   RNN  test loss = {rnn_test:.6}");
+    println!("   LSTM test loss = {lstm_test:.6}");
+
+    println!("\n--- Verification ---");
+    if rnn_err < 1e-6 && lstm_err < 1e-6 {
+        println!("PASS: BPTT gradients match finite differences");
+    } else {
+        println!("FAIL: gradient mismatch");
+    }
+    if rnn_test < 1e-3 && lstm_test < 1e-3 {
+        println!("PASS: both cells learned to count bits");
+    } else {
+        println!("Need more training iterations");
+    }
+}
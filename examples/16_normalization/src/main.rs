@@ -0,0 +1,405 @@
+//! Example 16: Layer Normalization and Batch Normalization
+//!
+//! Both layers standardize activations and then apply a learned gain and bias:
+//! y = γ (x - μ) / √(σ² + ε) + β
+//!
+//! - Layer norm: μ, σ² over the features of each sample
+//! - Batch norm: μ, σ² over the batch for each feature (training mode),
+//!   or running estimates of them (inference mode)
+//!
+//! Gradients w.r.t. inputs, gain and bias are checked against the analytic
+//! backward formulas.
+//!
+//! Run with: RUSTFLAGS="-Z autodiff=Enable" cargo +enzyme run -p normalization
+
+#![feature(autodiff)]
+
+use std::autodiff::autodiff_reverse;
+
+const BATCH: usize = 4;
+const FEATURES: usize = 5;
+const EPS: f64 = 1e-5;
+const MOMENTUM: f64 = 0.1;
+
+/// Newton-Raphson sqrt with range reduction
+/// Scales x by powers of 4 into [0.25, 1] first, so a handful of iterations
+/// converge for any magnitude (e.g. σ² + ε with σ² tiny).
+fn my_sqrt(x: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    let mut m = x;
+    let mut scale = 1.0;
+    while m > 1.0 {
+        m /= 4.0;
+        scale *= 2.0;
+    }
+    while m < 0.25 {
+        m *= 4.0;
+        scale /= 2.0;
+    }
+    let mut guess = 1.0;
+    let mut i = 0;
+    while i < 8 {
+        guess = (guess + m / guess) / 2.0;
+        i += 1;
+    }
+    guess * scale
+}
+
+/// Layer normalization of each row of a BATCH × FEATURES matrix
+/// params = [gain (FEATURES), bias (FEATURES)]
+fn layer_norm(x: &[f64], params: &[f64], out: &mut [f64]) {
+    let n = FEATURES as f64;
+    let mut b = 0;
+    while b < BATCH {
+        let row = b * FEATURES;
+        let mut mean = 0.0;
+        let mut i = 0;
+        while i < FEATURES {
+            mean += x[row + i];
+            i += 1;
+        }
+        mean /= n;
+
+        let mut var = 0.0;
+        let mut i = 0;
+        while i < FEATURES {
+            let d = x[row + i] - mean;
+            var += d * d;
+            i += 1;
+        }
+        var /= n;
+
+        let inv_std = 1.0 / my_sqrt(var + EPS);
+        let mut i = 0;
+        while i < FEATURES {
+            out[row + i] = params[i] * (x[row + i] - mean) * inv_std + params[FEATURES + i];
+            i += 1;
+        }
+        b += 1;
+    }
+}
+
+/// Per-feature batch mean and (biased) variance: stats = [mean (FEATURES), var (FEATURES)]
+fn batch_stats(x: &[f64], stats: &mut [f64]) {
+    let n = BATCH as f64;
+    let mut i = 0;
+    while i < FEATURES {
+        let mut mean = 0.0;
+        let mut b = 0;
+        while b < BATCH {
+            mean += x[b * FEATURES + i];
+            b += 1;
+        }
+        mean /= n;
+
+        let mut var = 0.0;
+        let mut b = 0;
+        while b < BATCH {
+            let d = x[b * FEATURES + i] - mean;
+            var += d * d;
+            b += 1;
+        }
+        stats[i] = mean;
+        stats[FEATURES + i] = var / n;
+        i += 1;
+    }
+}
+
+/// Batch normalization, training mode: normalize with the current batch statistics
+/// params = [gamma (FEATURES), beta (FEATURES)]
+fn batch_norm_train(x: &[f64], params: &[f64], out: &mut [f64]) {
+    let mut stats = [0.0; 2 * FEATURES];
+    batch_stats(x, &mut stats);
+    batch_norm_infer(x, params, &stats, out);
+}
+
+/// Batch normalization, inference mode: normalize with fixed statistics
+/// running = [mean (FEATURES), var (FEATURES)]
+fn batch_norm_infer(x: &[f64], params: &[f64], running: &[f64], out: &mut [f64]) {
+    let mut i = 0;
+    while i < FEATURES {
+        let inv_std = 1.0 / my_sqrt(running[FEATURES + i] + EPS);
+        let mut b = 0;
+        while b < BATCH {
+            let idx = b * FEATURES + i;
+            out[idx] = params[i] * (x[idx] - running[i]) * inv_std + params[FEATURES + i];
+            b += 1;
+        }
+        i += 1;
+    }
+}
+
+/// Running statistics update (not differentiated):
+/// running ← (1 - momentum) · running + momentum · batch statistics
+/// The variance estimate uses the unbiased batch variance, as in PyTorch.
+fn update_running_stats(x: &[f64], running: &mut [f64]) {
+    let mut stats = [0.0; 2 * FEATURES];
+    batch_stats(x, &mut stats);
+    let unbias = BATCH as f64 / (BATCH - 1) as f64;
+    for i in 0..FEATURES {
+        running[i] = (1.0 - MOMENTUM) * running[i] + MOMENTUM * stats[i];
+        running[FEATURES + i] =
+            (1.0 - MOMENTUM) * running[FEATURES + i] + MOMENTUM * unbias * stats[FEATURES + i];
+    }
+}
+
+/// Σ upstreamᵢ · yᵢ with y = layer_norm(x)
+/// Reverse mode on this scalar gives the vector-Jacobian product with `upstream`,
+/// i.e. the gradients a layer receives during backpropagation.
+#[autodiff_reverse(d_layer_norm_probe, Duplicated, Duplicated, Const, Active)]
+fn layer_norm_probe(x: &[f64], params: &[f64], upstream: &[f64]) -> f64 {
+    let mut y = [0.0; BATCH * FEATURES];
+    layer_norm(x, params, &mut y);
+    let mut sum = 0.0;
+    let mut i = 0;
+    while i < BATCH * FEATURES {
+        sum += upstream[i] * y[i];
+        i += 1;
+    }
+    sum
+}
+
+/// Σ upstreamᵢ · yᵢ with y = batch_norm_train(x)
+#[autodiff_reverse(d_batch_norm_train_probe, Duplicated, Duplicated, Const, Active)]
+fn batch_norm_train_probe(x: &[f64], params: &[f64], upstream: &[f64]) -> f64 {
+    let mut y = [0.0; BATCH * FEATURES];
+    batch_norm_train(x, params, &mut y);
+    let mut sum = 0.0;
+    let mut i = 0;
+    while i < BATCH * FEATURES {
+        sum += upstream[i] * y[i];
+        i += 1;
+    }
+    sum
+}
+
+/// Σ upstreamᵢ · yᵢ with y = batch_norm_infer(x); running statistics are constant
+#[autodiff_reverse(d_batch_norm_infer_probe, Duplicated, Duplicated, Const, Const, Active)]
+fn batch_norm_infer_probe(x: &[f64], params: &[f64], running: &[f64], upstream: &[f64]) -> f64 {
+    let mut y = [0.0; BATCH * FEATURES];
+    batch_norm_infer(x, params, running, &mut y);
+    let mut sum = 0.0;
+    let mut i = 0;
+    while i < BATCH * FEATURES {
+        sum += upstream[i] * y[i];
+        i += 1;
+    }
+    sum
+}
+
+/// Analytic backward pass of normalization over one group of elements
+/// (a row for layer norm, a column for batch norm). With x̂ the normalized
+/// input and g = γ ⊙ dy:
+///
+/// dx = (1/σ) (g - mean(g) - x̂ · mean(g ⊙ x̂))
+///
+/// `idx` lists the positions of the group inside the flat arrays and `gains`
+/// the γ applied at each of them. Returns x̂, from which dγ = Σ dy ⊙ x̂.
+fn analytic_group_backward(
+    x: &[f64],
+    upstream: &[f64],
+    gains: &[f64],
+    idx: &[usize],
+    dx: &mut [f64],
+) -> Vec<f64> {
+    let n = idx.len() as f64;
+    let mean = idx.iter().map(|&k| x[k]).sum::<f64>() / n;
+    let var = idx.iter().map(|&k| (x[k] - mean).powi(2)).sum::<f64>() / n;
+    let inv_std = 1.0 / (var + EPS).sqrt();
+    let x_hat: Vec<f64> = idx.iter().map(|&k| (x[k] - mean) * inv_std).collect();
+
+    let g: Vec<f64> = idx
+        .iter()
+        .zip(gains)
+        .map(|(&k, gain)| gain * upstream[k])
+        .collect();
+    let mean_g = g.iter().sum::<f64>() / n;
+    let mean_g_xhat = g.iter().zip(&x_hat).map(|(g, h)| g * h).sum::<f64>() / n;
+
+    for (j, &k) in idx.iter().enumerate() {
+        dx[k] = inv_std * (g[j] - mean_g - x_hat[j] * mean_g_xhat);
+    }
+    x_hat
+}
+
+/// Analytic layer-norm gradients; returns (dx, d_params)
+fn layer_norm_analytic(x: &[f64], params: &[f64], upstream: &[f64]) -> (Vec<f64>, Vec<f64>) {
+    let mut dx = vec![0.0; BATCH * FEATURES];
+    let mut d_params = vec![0.0; 2 * FEATURES];
+    for b in 0..BATCH {
+        let row: Vec<usize> = (0..FEATURES).map(|i| b * FEATURES + i).collect();
+        let x_hat = analytic_group_backward(x, upstream, &params[..FEATURES], &row, &mut dx);
+        for i in 0..FEATURES {
+            d_params[i] += upstream[row[i]] * x_hat[i];
+            d_params[FEATURES + i] += upstream[row[i]];
+        }
+    }
+    (dx, d_params)
+}
+
+/// Analytic batch-norm (training mode) gradients; returns (dx, d_params)
+fn batch_norm_analytic(x: &[f64], params: &[f64], upstream: &[f64]) -> (Vec<f64>, Vec<f64>) {
+    let mut dx = vec![0.0; BATCH * FEATURES];
+    let mut d_params = vec![0.0; 2 * FEATURES];
+    for i in 0..FEATURES {
+        let column: Vec<usize> = (0..BATCH).map(|b| b * FEATURES + i).collect();
+        let gains = [params[i]; BATCH];
+        let x_hat = analytic_group_backward(x, upstream, &gains, &column, &mut dx);
+        for b in 0..BATCH {
+            d_params[i] += upstream[column[b]] * x_hat[b];
+            d_params[FEATURES + i] += upstream[column[b]];
+        }
+    }
+    (dx, d_params)
+}
+
+fn max_abs_diff(a: &[f64], b: &[f64]) -> f64 {
+    a.iter()
+        .zip(b)
+        .map(|(x, y)| (x - y).abs())
+        .fold(0.0, f64::max)
+}
+
+/// Deterministic pseudo-random numbers in [0, 1) (64-bit LCG)
+fn next_uniform(state: &mut u64) -> f64 {
+    *state = state
+        .wrapping_mul(6364136223846793005)
+        .wrapping_add(1442695040888963407);
+    (*state >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// A batch whose features have very different scales and offsets
+fn sample_batch(rng: &mut u64) -> Vec<f64> {
+    let offsets = [5.0, -2.0, 0.0, 10.0, 0.5];
+    let scales = [3.0, 0.5, 1.0, 8.0, 0.01];
+    let mut x = vec![0.0; BATCH * FEATURES];
+    for b in 0..BATCH {
+        for i in 0..FEATURES {
+            x[b * FEATURES + i] = offsets[i] + scales[i] * (2.0 * next_uniform(rng) - 1.0);
+        }
+    }
+    x
+}
+
+fn main() {
+    println!("Layer Normalization and Batch Normalization");
+    println!("===========================================\n");
+
+    let mut rng = 7;
+    let x = sample_batch(&mut rng);
+    let upstream: Vec<f64> = (0..BATCH * FEATURES)
+        .map(|_| 2.0 * next_uniform(&mut rng) - 1.0)
+        .collect();
+    let params = [1.5, 0.5, 1.0, 2.0, 0.8, 0.1, -0.2, 0.0, 0.3, 1.0];
+
+    // 1. Layer norm
+    println!("1. Layer norm (per sample, over {FEATURES} features)");
+    let identity = [1.0, 1.0, 1.0, 1.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0];
+    let mut y = [0.0; BATCH * FEATURES];
+    layer_norm(&x, &identity, &mut y);
+    let row_mean = y[..FEATURES].iter().sum::<f64>() / FEATURES as f64;
+    let row_var = y[..FEATURES]
+        .iter()
+        .map(|v| (v - row_mean).powi(2))
+        .sum::<f64>()
+        / FEATURES as f64;
+    println!("   row 0 after LN (γ=1, β=0): mean = {row_mean:.2e}, var = {row_var:.6}");
+
+    let mut dx = [0.0; BATCH * FEATURES];
+    let mut d_params = [0.0; 2 * FEATURES];
+    let _ = d_layer_norm_probe(&x, &mut dx, &params, &mut d_params, &upstream, 1.0);
+    let (dx_ref, d_params_ref) = layer_norm_analytic(&x, &params, &upstream);
+    let ln_err_x = max_abs_diff(&dx, &dx_ref);
+    let ln_err_p = max_abs_diff(&d_params, &d_params_ref);
+    println!("   max |dx - analytic|      = {ln_err_x:.2e}");
+    println!("   max |dγ,dβ - analytic|   = {ln_err_p:.2e}");
+    println!();
+
+    // 2. Batch norm, training mode
+    println!("2. Batch norm, training mode (per feature, over a batch of {BATCH})");
+    let mut dx = [0.0; BATCH * FEATURES];
+    let mut d_params = [0.0; 2 * FEATURES];
+    let _ = d_batch_norm_train_probe(&x, &mut dx, &params, &mut d_params, &upstream, 1.0);
+    let (dx_ref, d_params_ref) = batch_norm_analytic(&x, &params, &upstream);
+    let bn_err_x = max_abs_diff(&dx, &dx_ref);
+    let bn_err_p = max_abs_diff(&d_params, &d_params_ref);
+    println!("   max |dx - analytic|      = {bn_err_x:.2e}");
+    println!("   max |dγ,dβ - analytic|   = {bn_err_p:.2e}");
+
+    // Σ dx over the batch is zero: shifting every sample moves μ by the same amount
+    let col_sum = (0..BATCH).map(|b| dx[b * FEATURES]).sum::<f64>();
+    println!("   Σ_batch dx[:, 0]         = {col_sum:.2e} (expected: 0)");
+    println!();
+
+    // 3. Running statistics
+    println!("3. Running statistics (momentum = {MOMENTUM})");
+    let mut running = [0.0; 2 * FEATURES];
+    for i in 0..FEATURES {
+        running[FEATURES + i] = 1.0;
+    }
+    for _ in 0..200 {
+        let batch = sample_batch(&mut rng);
+        update_running_stats(&batch, &mut running);
+    }
+    let fmt = |v: &[f64]| {
+        v.iter()
+            .map(|x| format!("{x:8.4}"))
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    // Variance of uniform noise on [-s, s] is s²/3
+    println!("   running mean: {}", fmt(&running[..FEATURES]));
+    println!("   population:   {}", fmt(&[5.0, -2.0, 0.0, 10.0, 0.5]));
+    println!("   running var:  {}", fmt(&running[FEATURES..]));
+    println!(
+        "   population:   {}",
+        fmt(&[3.0, 0.25 / 3.0, 1.0 / 3.0, 64.0 / 3.0, 1e-4 / 3.0])
+    );
+    println!();
+
+    // 4. Batch norm, inference mode: y = γ (x - μ_run)/√(σ²_run + ε) + β
+    println!("4. Batch norm, inference mode");
+    let mut dx = [0.0; BATCH * FEATURES];
+    let mut d_params = [0.0; 2 * FEATURES];
+    let _ = d_batch_norm_infer_probe(
+        &x,
+        &mut dx,
+        &params,
+        &mut d_params,
+        &running,
+        &upstream,
+        1.0,
+    );
+    let mut infer_err: f64 = 0.0;
+    for b in 0..BATCH {
+        for i in 0..FEATURES {
+            let k = b * FEATURES + i;
+            let inv_std = 1.0 / (running[FEATURES + i] + EPS).sqrt();
+            infer_err = infer_err.max((dx[k] - params[i] * upstream[k] * inv_std).abs());
+        }
+    }
+    for i in 0..FEATURES {
+        let inv_std = 1.0 / (running[FEATURES + i] + EPS).sqrt();
+        let (mut d_gamma, mut d_beta) = (0.0, 0.0);
+        for b in 0..BATCH {
+            let k = b * FEATURES + i;
+            d_gamma += upstream[k] * (x[k] - running[i]) * inv_std;
+            d_beta += upstream[k];
+        }
+        infer_err = infer_err.max((d_params[i] - d_gamma).abs());
+        infer_err = infer_err.max((d_params[FEATURES + i] - d_beta).abs());
+    }
+    println!("   dx = γ·dy/√(σ²_run + ε): max error = {infer_err:.2e}");
+
+    println!("\n--- Verification ---");
+    let worst = [ln_err_x, ln_err_p, bn_err_x, bn_err_p, infer_err]
+        .into_iter()
+        .fold(0.0, f64::max);
+    if worst < 1e-8 {
+        println!("PASS: all normalization gradients match the analytic formulas");
+    } else {
+        println!("FAIL: max gradient error {worst:.2e}");
+    }
+}
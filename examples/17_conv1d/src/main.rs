@@ -0,0 +1,537 @@
+//! Example 17: 1D Convolution and Pooling Layers
+//!
+//! Implements a 1D convolution with configurable kernel size, stride, padding
+//! and channel counts, plus max and average pooling. Gradients w.r.t. the
+//! input signal and the kernel come from a single reverse pass (`Duplicated`).
+//!
+//! Demo: classify noisy sine waves as low or high frequency with
+//! conv → ReLU → max pool → global average pool → logistic regression.
+//!
+//! Run with: RUSTFLAGS="-Z autodiff=Enable" cargo +enzyme run -p conv1d
+
+#![feature(autodiff)]
+
+use std::autodiff::autodiff_reverse;
+
+/// Upper bound on intermediate buffer sizes inside differentiated functions;
+/// the probes assert that their outputs fit
+const MAX_BUF: usize = 256;
+
+/// Shape of a 1D convolution layer
+/// Kernel layout: [out_channels × in_channels × kernel_size], row-major
+#[derive(Clone, Copy)]
+struct Conv1d {
+    in_channels: usize,
+    out_channels: usize,
+    kernel_size: usize,
+    stride: usize,
+    padding: usize,
+}
+
+impl Conv1d {
+    /// L_out = (L + 2·padding - kernel_size) / stride + 1
+    ///
+    /// Panics unless stride ≥ 1 and kernel_size ≤ L + 2·padding.
+    fn output_len(&self, input_len: usize) -> usize {
+        assert!(self.stride >= 1, "stride must be at least 1");
+        assert!(
+            self.kernel_size <= input_len + 2 * self.padding,
+            "kernel size {} exceeds padded input length {}",
+            self.kernel_size,
+            input_len + 2 * self.padding
+        );
+        (input_len + 2 * self.padding - self.kernel_size) / self.stride + 1
+    }
+
+    fn kernel_len(&self) -> usize {
+        self.out_channels * self.in_channels * self.kernel_size
+    }
+}
+
+/// 1D convolution (cross-correlation, as in every deep learning framework)
+///
+/// out[o, t] = bias[o] + Σ_c Σ_k kernel[o, c, k] · input[c, t·stride + k - padding]
+///
+/// Positions outside the input read as zero (zero padding).
+/// `input` is [in_channels × input_len], `out` is [out_channels × output_len].
+fn conv1d(
+    input: &[f64],
+    input_len: usize,
+    kernel: &[f64],
+    bias: &[f64],
+    cfg: &Conv1d,
+    out: &mut [f64],
+) {
+    let out_len = cfg.output_len(input_len);
+    let mut o = 0;
+    while o < cfg.out_channels {
+        let mut t = 0;
+        while t < out_len {
+            let mut sum = bias[o];
+            let mut c = 0;
+            while c < cfg.in_channels {
+                let mut k = 0;
+                while k < cfg.kernel_size {
+                    // Signed arithmetic for the padded index
+                    let pos = (t * cfg.stride + k) as isize - cfg.padding as isize;
+                    if pos >= 0 && (pos as usize) < input_len {
+                        let w = kernel[(o * cfg.in_channels + c) * cfg.kernel_size + k];
+                        sum += w * input[c * input_len + pos as usize];
+                    }
+                    k += 1;
+                }
+                c += 1;
+            }
+            out[o * out_len + t] = sum;
+            t += 1;
+        }
+        o += 1;
+    }
+}
+
+/// Number of pooling windows: (len - size) / stride + 1
+///
+/// Panics unless stride ≥ 1 and 1 ≤ size ≤ len.
+fn pool_output_len(len: usize, size: usize, stride: usize) -> usize {
+    assert!(stride >= 1, "stride must be at least 1");
+    assert!(
+        size >= 1 && size <= len,
+        "window size {size} must be between 1 and the length {len}"
+    );
+    (len - size) / stride + 1
+}
+
+/// Max pooling over windows of `size` with step `stride`, per channel
+/// The gradient flows only to the (first) maximum of each window.
+fn max_pool1d(x: &[f64], channels: usize, len: usize, size: usize, stride: usize, out: &mut [f64]) {
+    let out_len = pool_output_len(len, size, stride);
+    let mut c = 0;
+    while c < channels {
+        let mut t = 0;
+        while t < out_len {
+            let start = c * len + t * stride;
+            let mut best = x[start];
+            let mut k = 1;
+            while k < size {
+                if x[start + k] > best {
+                    best = x[start + k];
+                }
+                k += 1;
+            }
+            out[c * out_len + t] = best;
+            t += 1;
+        }
+        c += 1;
+    }
+}
+
+/// Average pooling over windows of `size` with step `stride`, per channel
+/// The gradient is spread evenly (1/size) over each window.
+fn avg_pool1d(x: &[f64], channels: usize, len: usize, size: usize, stride: usize, out: &mut [f64]) {
+    let out_len = pool_output_len(len, size, stride);
+    let mut c = 0;
+    while c < channels {
+        let mut t = 0;
+        while t < out_len {
+            let start = c * len + t * stride;
+            let mut sum = 0.0;
+            let mut k = 0;
+            while k < size {
+                sum += x[start + k];
+                k += 1;
+            }
+            out[c * out_len + t] = sum / size as f64;
+            t += 1;
+        }
+        c += 1;
+    }
+}
+
+/// Σ upstreamᵢ · yᵢ with y = conv1d(input, kernel), zero bias
+/// Its gradient is the vector-Jacobian product w.r.t. both input and kernel.
+#[autodiff_reverse(d_conv1d_probe, Duplicated, Duplicated, Const, Const, Active)]
+fn conv1d_probe(input: &[f64], kernel: &[f64], cfg: &Conv1d, upstream: &[f64]) -> f64 {
+    assert!(
+        cfg.in_channels >= 1 && input.len().is_multiple_of(cfg.in_channels),
+        "input of {} values is not a whole number of {} channels",
+        input.len(),
+        cfg.in_channels
+    );
+    assert!(
+        kernel.len() == cfg.kernel_len(),
+        "kernel has {} values, expected {}",
+        kernel.len(),
+        cfg.kernel_len()
+    );
+    let input_len = input.len() / cfg.in_channels;
+    let out_size = cfg.out_channels * cfg.output_len(input_len);
+    assert!(
+        out_size <= MAX_BUF && cfg.out_channels <= MAX_BUF,
+        "output of {out_size} values exceeds MAX_BUF = {MAX_BUF}"
+    );
+    assert!(
+        upstream.len() <= out_size,
+        "upstream longer than the output"
+    );
+    let bias = [0.0; MAX_BUF];
+    let mut y = [0.0; MAX_BUF];
+    conv1d(input, input_len, kernel, &bias, cfg, &mut y);
+    let mut sum = 0.0;
+    let mut i = 0;
+    while i < upstream.len() {
+        sum += upstream[i] * y[i];
+        i += 1;
+    }
+    sum
+}
+
+/// Σ upstreamᵢ · yᵢ with y = max_pool1d(x) (one channel, size 3, stride 2)
+#[autodiff_reverse(d_max_pool_probe, Duplicated, Const, Active)]
+fn max_pool_probe(x: &[f64], upstream: &[f64]) -> f64 {
+    let out_len = pool_output_len(x.len(), 3, 2);
+    assert!(
+        out_len <= MAX_BUF,
+        "output of {out_len} values exceeds MAX_BUF = {MAX_BUF}"
+    );
+    assert!(upstream.len() <= out_len, "upstream longer than the output");
+    let mut y = [0.0; MAX_BUF];
+    max_pool1d(x, 1, x.len(), 3, 2, &mut y);
+    let mut sum = 0.0;
+    let mut i = 0;
+    while i < upstream.len() {
+        sum += upstream[i] * y[i];
+        i += 1;
+    }
+    sum
+}
+
+/// Σ upstreamᵢ · yᵢ with y = avg_pool1d(x) (one channel, size 3, stride 2)
+#[autodiff_reverse(d_avg_pool_probe, Duplicated, Const, Active)]
+fn avg_pool_probe(x: &[f64], upstream: &[f64]) -> f64 {
+    let out_len = pool_output_len(x.len(), 3, 2);
+    assert!(
+        out_len <= MAX_BUF,
+        "output of {out_len} values exceeds MAX_BUF = {MAX_BUF}"
+    );
+    assert!(upstream.len() <= out_len, "upstream longer than the output");
+    let mut y = [0.0; MAX_BUF];
+    avg_pool1d(x, 1, x.len(), 3, 2, &mut y);
+    let mut sum = 0.0;
+    let mut i = 0;
+    while i < upstream.len() {
+        sum += upstream[i] * y[i];
+        i += 1;
+    }
+    sum
+}
+
+// Classifier: conv(1 → 4, k=5, pad=2) → ReLU → max pool(4, 4) → global average → logit
+const SIGNAL_LEN: usize = 32;
+const CLASSIFIER_CONV: Conv1d = Conv1d {
+    in_channels: 1,
+    out_channels: 4,
+    kernel_size: 5,
+    stride: 1,
+    padding: 2,
+};
+const POOL: usize = 4;
+
+// Parameter layout of the classifier (one flat slice)
+const P_KERNEL: usize = 0; // [4 × 1 × 5]
+const P_CONV_BIAS: usize = P_KERNEL + 4 * 5; // [4]
+const P_DENSE_W: usize = P_CONV_BIAS + 4; // [4]
+const P_DENSE_B: usize = P_DENSE_W + 4; // [1]
+const N_PARAMS: usize = P_DENSE_B + 1;
+
+/// exp(x) via argument halving and a Taylor series
+fn my_exp(x: f64) -> f64 {
+    let mut r = x;
+    let mut halvings = 0;
+    while (if r < 0.0 { -r } else { r }) > 0.5 {
+        r /= 2.0;
+        halvings += 1;
+    }
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    let mut k = 1;
+    while k < 20 {
+        term *= r / k as f64;
+        sum += term;
+        k += 1;
+    }
+    let mut i = 0;
+    while i < halvings {
+        sum *= sum;
+        i += 1;
+    }
+    sum
+}
+
+/// ln(1 + u) for u in [0, 1], via ln(1 + u) = 2·atanh(u / (2 + u))
+fn my_ln1p(u: f64) -> f64 {
+    let s = u / (2.0 + u);
+    let s2 = s * s;
+    let mut term = s;
+    let mut sum = 0.0;
+    let mut n = 0;
+    while n < 30 {
+        sum += term / (2 * n + 1) as f64;
+        term *= s2;
+        n += 1;
+    }
+    2.0 * sum
+}
+
+/// softplus(z) = ln(1 + eᶻ) = max(z, 0) + ln(1 + e^{-|z|})
+fn softplus(z: f64) -> f64 {
+    if z > 0.0 {
+        z + my_ln1p(my_exp(-z))
+    } else {
+        my_ln1p(my_exp(z))
+    }
+}
+
+/// Classifier logit for one signal
+fn classify(params: &[f64], signal: &[f64]) -> f64 {
+    let cfg = CLASSIFIER_CONV;
+    let mut conv = [0.0; 4 * SIGNAL_LEN];
+    conv1d(
+        signal,
+        SIGNAL_LEN,
+        &params[P_KERNEL..P_CONV_BIAS],
+        &params[P_CONV_BIAS..P_DENSE_W],
+        &cfg,
+        &mut conv,
+    );
+
+    // ReLU
+    let mut i = 0;
+    while i < 4 * SIGNAL_LEN {
+        if conv[i] < 0.0 {
+            conv[i] = 0.0;
+        }
+        i += 1;
+    }
+
+    let pooled_len = SIGNAL_LEN / POOL;
+    let mut pooled = [0.0; 4 * SIGNAL_LEN / POOL];
+    max_pool1d(&conv, 4, SIGNAL_LEN, POOL, POOL, &mut pooled);
+
+    // Global average pool: one feature per channel
+    let mut features = [0.0; 4];
+    avg_pool1d(&pooled, 4, pooled_len, pooled_len, 1, &mut features);
+
+    let mut logit = params[P_DENSE_B];
+    let mut c = 0;
+    while c < 4 {
+        logit += params[P_DENSE_W + c] * features[c];
+        c += 1;
+    }
+    logit
+}
+
+/// Mean logistic loss over a set of signals with labels ±1
+#[autodiff_reverse(d_classifier_loss, Duplicated, Const, Const, Active)]
+fn classifier_loss(params: &[f64], signals: &[f64], labels: &[f64]) -> f64 {
+    let n = labels.len();
+    let mut loss = 0.0;
+    let mut s = 0;
+    while s < n {
+        let signal = &signals[s * SIGNAL_LEN..(s + 1) * SIGNAL_LEN];
+        loss += softplus(-labels[s] * classify(params, signal));
+        s += 1;
+    }
+    loss / n as f64
+}
+
+/// Deterministic pseudo-random numbers in [0, 1) (64-bit LCG)
+fn next_uniform(state: &mut u64) -> f64 {
+    *state = state
+        .wrapping_mul(6364136223846793005)
+        .wrapping_add(1442695040888963407);
+    (*state >> 11) as f64 / (1u64 << 53) as f64
+}
+
+fn random_vec(n: usize, scale: f64, rng: &mut u64) -> Vec<f64> {
+    (0..n)
+        .map(|_| scale * (2.0 * next_uniform(rng) - 1.0))
+        .collect()
+}
+
+/// Noisy sine waves: label -1 for 1-2 cycles per window, +1 for 5-7 cycles
+fn make_signals(n: usize, rng: &mut u64) -> (Vec<f64>, Vec<f64>) {
+    let mut signals = Vec::with_capacity(n * SIGNAL_LEN);
+    let mut labels = Vec::with_capacity(n);
+    for s in 0..n {
+        let high = s % 2 == 1;
+        let cycles = if high {
+            5.0 + 2.0 * next_uniform(rng)
+        } else {
+            1.0 + next_uniform(rng)
+        };
+        let phase = 2.0 * std::f64::consts::PI * next_uniform(rng);
+        for t in 0..SIGNAL_LEN {
+            let arg = 2.0 * std::f64::consts::PI * cycles * t as f64 / SIGNAL_LEN as f64;
+            let noise = 0.2 * (2.0 * next_uniform(rng) - 1.0);
+            signals.push((arg + phase).sin() + noise);
+        }
+        labels.push(if high { 1.0 } else { -1.0 });
+    }
+    (signals, labels)
+}
+
+/// Largest |autodiff - central difference| for one argument of a probe
+fn max_fd_error(f: &dyn Fn(&[f64]) -> f64, x: &[f64], grad: &[f64]) -> f64 {
+    let h = 1e-6;
+    let mut worst: f64 = 0.0;
+    for i in 0..x.len() {
+        let mut plus = x.to_vec();
+        let mut minus = x.to_vec();
+        plus[i] += h;
+        minus[i] -= h;
+        let fd = (f(&plus) - f(&minus)) / (2.0 * h);
+        worst = worst.max((fd - grad[i]).abs());
+    }
+    worst
+}
+
+fn accuracy(params: &[f64], signals: &[f64], labels: &[f64]) -> f64 {
+    let correct = labels
+        .iter()
+        .enumerate()
+        .filter(|(s, &label)| {
+            let logit = classify(params, &signals[s * SIGNAL_LEN..(s + 1) * SIGNAL_LEN]);
+            logit * label > 0.0
+        })
+        .count();
+    correct as f64 / labels.len() as f64
+}
+
+fn main() {
+    println!("1D Convolution and Pooling");
+    println!("==========================\n");
+
+    let mut rng = 2024;
+
+    // 1. Gradient checks on a layer with stride and padding
+    let cfg = Conv1d {
+        in_channels: 2,
+        out_channels: 3,
+        kernel_size: 4,
+        stride: 2,
+        padding: 1,
+    };
+    let input_len = 11;
+    let out_len = cfg.output_len(input_len);
+    println!("1. Gradient checks (autodiff vs finite differences)");
+    println!(
+        "   conv: {} → {} channels, kernel {}, stride {}, padding {}, length {} → {}",
+        cfg.in_channels,
+        cfg.out_channels,
+        cfg.kernel_size,
+        cfg.stride,
+        cfg.padding,
+        input_len,
+        out_len
+    );
+
+    let input = random_vec(cfg.in_channels * input_len, 1.0, &mut rng);
+    let kernel = random_vec(cfg.kernel_len(), 1.0, &mut rng);
+    let upstream = random_vec(cfg.out_channels * out_len, 1.0, &mut rng);
+
+    let mut d_input = vec![0.0; input.len()];
+    let mut d_kernel = vec![0.0; kernel.len()];
+    let _ = d_conv1d_probe(
+        &input,
+        &mut d_input,
+        &kernel,
+        &mut d_kernel,
+        &cfg,
+        &upstream,
+        1.0,
+    );
+    let err_input = max_fd_error(
+        &|x| conv1d_probe(x, &kernel, &cfg, &upstream),
+        &input,
+        &d_input,
+    );
+    let err_kernel = max_fd_error(
+        &|k| conv1d_probe(&input, k, &cfg, &upstream),
+        &kernel,
+        &d_kernel,
+    );
+    println!("   conv ∂/∂input:  max error = {err_input:.2e}");
+    println!("   conv ∂/∂kernel: max error = {err_kernel:.2e}");
+
+    let x = random_vec(13, 1.0, &mut rng);
+    let upstream = random_vec((13 - 3) / 2 + 1, 1.0, &mut rng);
+    let mut dx = vec![0.0; x.len()];
+    let _ = d_max_pool_probe(&x, &mut dx, &upstream, 1.0);
+    let err_max = max_fd_error(&|x| max_pool_probe(x, &upstream), &x, &dx);
+    println!("   max pool:       max error = {err_max:.2e}");
+
+    let mut dx = vec![0.0; x.len()];
+    let _ = d_avg_pool_probe(&x, &mut dx, &upstream, 1.0);
+    let err_avg = max_fd_error(&|x| avg_pool_probe(x, &upstream), &x, &dx);
+    println!("   avg pool:       max error = {err_avg:.2e}");
+    println!();
+
+    // 2. Signal classification
+    println!("2. Signal classification (low vs high frequency)");
+    let (train_x, train_y) = make_signals(64, &mut rng);
+    let (test_x, test_y) = make_signals(32, &mut rng);
+
+    let mut params = random_vec(N_PARAMS, 0.5, &mut rng);
+    let mut grad = vec![0.0; N_PARAMS];
+    let _ = d_classifier_loss(&params, &mut grad, &train_x, &train_y, 1.0);
+    let err_model = max_fd_error(&|p| classifier_loss(p, &train_x, &train_y), &params, &grad);
+    println!("   full model ∂/∂params: max error = {err_model:.2e}");
+    println!(
+        "   initial test accuracy = {:.1}%",
+        100.0 * accuracy(&params, &test_x, &test_y)
+    );
+
+    // Adam
+    let (beta1, beta2, epsilon, lr) = (0.9, 0.999, 1e-8, 0.05);
+    let mut m = vec![0.0; N_PARAMS];
+    let mut v = vec![0.0; N_PARAMS];
+    let n_iters = 300;
+    for iter in 0..n_iters {
+        let mut grad = vec![0.0; N_PARAMS];
+        let loss = d_classifier_loss(&params, &mut grad, &train_x, &train_y, 1.0);
+        for i in 0..N_PARAMS {
+            m[i] = beta1 * m[i] + (1.0 - beta1) * grad[i];
+            v[i] = beta2 * v[i] + (1.0 - beta2) * grad[i] * grad[i];
+            let m_hat = m[i] / (1.0 - f64::powi(beta1, iter + 1));
+            let v_hat = v[i] / (1.0 - f64::powi(beta2, iter + 1));
+            params[i] -= lr * m_hat / (v_hat.sqrt() + epsilon);
+        }
+        if iter % 50 == 0 || iter == n_iters - 1 {
+            println!("   iter {iter:3}: loss = {loss:.6}");
+        }
+    }
+    let test_acc = accuracy(&params, &test_x, &test_y);
+    println!("   final test accuracy = {:.1}%", 100.0 * test_acc);
+    println!(
+        "   learned kernels: {:?}",
+        params[P_KERNEL..P_CONV_BIAS]
+            .chunks(5)
+            .map(|k| k.iter().map(|w| (w * 100.0).round() / 100.0).collect())
+            .collect::<Vec<Vec<f64>>>()
+    );
+
+    println!("\n--- Verification ---");
+    let worst = [err_input, err_kernel, err_max, err_avg, err_model]
+        .into_iter()
+        .fold(0.0, f64::max);
+    if worst < 1e-6 {
+        println!("PASS: conv and pooling gradients match finite differences");
+    } else {
+        println!("FAIL: max gradient error {worst:.2e}");
+    }
+    if test_acc > 0.95 {
+        println!("PASS: classifier separates low and high frequencies");
+    } else {
+        println!("Need more training iterations");
+    }
+}
@@ -0,0 +1,506 @@
+//! Example 18: N-Level Quantum System Simulator
+//!
+//! Generalizes the qubit propagator of example 14 to any dimension d up to
+//! `MAX_DIM` (raise it for larger systems):
+//!
+//! H(t) = H₀ + Σⱼ uⱼ(t) Hⱼ
+//!
+//! with the drift H₀ and control Hamiltonians Hⱼ given as complex d×d matrices.
+//! Controls are piecewise constant, and each step applies exp(-iHΔt) exactly
+//! (to machine precision) with a Taylor series and sub-stepping, written in
+//! Enzyme-friendly `while` loops. The gradient w.r.t. every control amplitude
+//! comes from one reverse pass.
+//!
+//! The same optimization loop then drives a qubit, a qutrit, two coupled
+//! qubits and a Kerr oscillator.
+//!
+//! Run with: RUSTFLAGS="-Z autodiff=Enable" cargo +enzyme run -p n_level_system
+
+#![feature(autodiff)]
+
+use std::autodiff::autodiff_reverse;
+
+/// Largest supported Hilbert-space dimension (sizes the stack buffers);
+/// `propagate_step` and `infidelity` assert d ≤ MAX_DIM
+const MAX_DIM: usize = 8;
+/// Taylor order for exp(-iHτ) with ‖Hτ‖ ≤ 1/2: truncation error < 1e-20
+const TAYLOR_ORDER: usize = 16;
+
+/// A d-level system driven by piecewise-constant controls
+///
+/// Complex numbers are stored as interleaved (re, im) pairs:
+/// - matrices are d×d, row-major: element (i, j) at 2·(i·d + j)
+/// - vectors have length d: element i at 2·i
+/// - control amplitudes: uⱼ during step k at k·n_controls + j
+struct System {
+    name: &'static str,
+    dim: usize,
+    n_controls: usize,
+    drift: Vec<f64>,
+    controls: Vec<f64>,
+    psi0: Vec<f64>,
+    target: Vec<f64>,
+    n_steps: usize,
+    dt: f64,
+}
+
+/// ψ ← exp(-iHΔt) ψ with H = H₀ + Σⱼ uⱼ Hⱼ
+///
+/// Splits Δt into 2ˢ sub-steps so that ‖H‖·τ ≤ 1/2 (‖·‖ the max row sum of
+/// |re| + |im|, an upper bound on the operator norm), then applies the
+/// Taylor series Σₙ (-iHτ)ⁿ/n! to the state on each sub-step.
+fn propagate_step(psi: &mut [f64], u: &[f64], sys: &System) {
+    let d = sys.dim;
+    assert!(d <= MAX_DIM, "dimension {d} exceeds MAX_DIM = {MAX_DIM}");
+
+    // Assemble H
+    let mut h = [0.0; 2 * MAX_DIM * MAX_DIM];
+    let mut e = 0;
+    while e < 2 * d * d {
+        let mut val = sys.drift[e];
+        let mut j = 0;
+        while j < sys.n_controls {
+            val += u[j] * sys.controls[j * 2 * d * d + e];
+            j += 1;
+        }
+        h[e] = val;
+        e += 1;
+    }
+
+    // Row-sum norm bound
+    let mut norm = 0.0;
+    let mut i = 0;
+    while i < d {
+        let mut row = 0.0;
+        let mut j = 0;
+        while j < d {
+            let re = h[2 * (i * d + j)];
+            let im = h[2 * (i * d + j) + 1];
+            row += (if re < 0.0 { -re } else { re }) + (if im < 0.0 { -im } else { im });
+            j += 1;
+        }
+        if row > norm {
+            norm = row;
+        }
+        i += 1;
+    }
+
+    let mut n_sub = 1;
+    while norm * sys.dt / n_sub as f64 > 0.5 {
+        n_sub *= 2;
+    }
+    let tau = sys.dt / n_sub as f64;
+
+    let mut s = 0;
+    while s < n_sub {
+        let mut term = [0.0; 2 * MAX_DIM];
+        let mut acc = [0.0; 2 * MAX_DIM];
+        let mut i = 0;
+        while i < 2 * d {
+            term[i] = psi[i];
+            acc[i] = psi[i];
+            i += 1;
+        }
+
+        let mut n = 1;
+        while n <= TAYLOR_ORDER {
+            // term ← (-iτ/n) H term
+            let c = tau / n as f64;
+            let mut next = [0.0; 2 * MAX_DIM];
+            let mut i = 0;
+            while i < d {
+                let mut v_re = 0.0;
+                let mut v_im = 0.0;
+                let mut j = 0;
+                while j < d {
+                    let h_re = h[2 * (i * d + j)];
+                    let h_im = h[2 * (i * d + j) + 1];
+                    v_re += h_re * term[2 * j] - h_im * term[2 * j + 1];
+                    v_im += h_re * term[2 * j + 1] + h_im * term[2 * j];
+                    j += 1;
+                }
+                // -i(v_re + i v_im) = v_im - i v_re
+                next[2 * i] = c * v_im;
+                next[2 * i + 1] = -c * v_re;
+                i += 1;
+            }
+            let mut i = 0;
+            while i < 2 * d {
+                term[i] = next[i];
+                acc[i] += next[i];
+                i += 1;
+            }
+            n += 1;
+        }
+
+        let mut i = 0;
+        while i < 2 * d {
+            psi[i] = acc[i];
+            i += 1;
+        }
+        s += 1;
+    }
+}
+
+/// State-transfer infidelity: 1 - |⟨ψ_target|ψ(T)⟩|²
+#[autodiff_reverse(d_infidelity, Duplicated, Const, Active)]
+fn infidelity(amps: &[f64], sys: &System) -> f64 {
+    let d = sys.dim;
+    assert!(d <= MAX_DIM, "dimension {d} exceeds MAX_DIM = {MAX_DIM}");
+    let mut psi = [0.0; 2 * MAX_DIM];
+    let mut i = 0;
+    while i < 2 * d {
+        psi[i] = sys.psi0[i];
+        i += 1;
+    }
+
+    let mut k = 0;
+    while k < sys.n_steps {
+        let start = k * sys.n_controls;
+        propagate_step(&mut psi, &amps[start..start + sys.n_controls], sys);
+        k += 1;
+    }
+
+    // ⟨target|ψ⟩ = Σ conj(tᵢ) ψᵢ
+    let mut ov_re = 0.0;
+    let mut ov_im = 0.0;
+    let mut i = 0;
+    while i < d {
+        let (t_re, t_im) = (sys.target[2 * i], sys.target[2 * i + 1]);
+        let (p_re, p_im) = (psi[2 * i], psi[2 * i + 1]);
+        ov_re += t_re * p_re + t_im * p_im;
+        ov_im += t_re * p_im - t_im * p_re;
+        i += 1;
+    }
+    1.0 - (ov_re * ov_re + ov_im * ov_im)
+}
+
+/// Final state ψ(T), for inspection outside the differentiated code
+fn final_state(amps: &[f64], sys: &System) -> Vec<f64> {
+    let mut psi = sys.psi0.clone();
+    for k in 0..sys.n_steps {
+        let start = k * sys.n_controls;
+        propagate_step(&mut psi, &amps[start..start + sys.n_controls], sys);
+    }
+    psi
+}
+
+// ---------------------------------------------------------------------------
+// Building systems (plain Rust, not differentiated)
+// ---------------------------------------------------------------------------
+
+/// Complex d×d zero matrix
+fn zeros(d: usize) -> Vec<f64> {
+    vec![0.0; 2 * d * d]
+}
+
+/// Sets element (i, j) of a complex matrix
+fn set(m: &mut [f64], d: usize, i: usize, j: usize, re: f64, im: f64) {
+    m[2 * (i * d + j)] = re;
+    m[2 * (i * d + j) + 1] = im;
+}
+
+/// a·A for a complex matrix A and real a
+fn scaled(a: f64, m: &[f64]) -> Vec<f64> {
+    m.iter().map(|x| a * x).collect()
+}
+
+/// a·A + b·B for complex matrices with real coefficients
+fn combine(a: f64, m_a: &[f64], b: f64, m_b: &[f64]) -> Vec<f64> {
+    m_a.iter().zip(m_b).map(|(x, y)| a * x + b * y).collect()
+}
+
+/// Kronecker product A ⊗ B of a dₐ×dₐ and a d_b×d_b complex matrix
+fn kron(m_a: &[f64], da: usize, m_b: &[f64], db: usize) -> Vec<f64> {
+    let d = da * db;
+    let mut out = zeros(d);
+    for i in 0..da {
+        for j in 0..da {
+            let (a_re, a_im) = (m_a[2 * (i * da + j)], m_a[2 * (i * da + j) + 1]);
+            for k in 0..db {
+                for l in 0..db {
+                    let (b_re, b_im) = (m_b[2 * (k * db + l)], m_b[2 * (k * db + l) + 1]);
+                    set(
+                        &mut out,
+                        d,
+                        i * db + k,
+                        j * db + l,
+                        a_re * b_re - a_im * b_im,
+                        a_re * b_im + a_im * b_re,
+                    );
+                }
+            }
+        }
+    }
+    out
+}
+
+/// Basis vector |n⟩ in dimension d
+fn basis(d: usize, n: usize) -> Vec<f64> {
+    let mut v = vec![0.0; 2 * d];
+    v[2 * n] = 1.0;
+    v
+}
+
+fn pauli_x() -> Vec<f64> {
+    vec![0.0, 0.0, 1.0, 0.0, 1.0, 0.0, 0.0, 0.0]
+}
+
+fn pauli_z() -> Vec<f64> {
+    vec![1.0, 0.0, 0.0, 0.0, 0.0, 0.0, -1.0, 0.0]
+}
+
+fn identity(d: usize) -> Vec<f64> {
+    let mut m = zeros(d);
+    for i in 0..d {
+        set(&mut m, d, i, i, 1.0, 0.0);
+    }
+    m
+}
+
+/// Example 14's model: H = ω₀σz/2 + u σx/2, |0⟩ → |1⟩
+fn qubit() -> System {
+    System {
+        name: "qubit",
+        dim: 2,
+        n_controls: 1,
+        drift: scaled(0.5, &pauli_z()),
+        controls: scaled(0.5, &pauli_x()),
+        psi0: basis(2, 0),
+        target: basis(2, 1),
+        n_steps: 100,
+        dt: 0.01,
+    }
+}
+
+/// Three-level ladder with detuned |2⟩ and x/y drives on both transitions,
+/// |0⟩ → |2⟩ (a two-photon transfer)
+fn qutrit() -> System {
+    let d = 3;
+    let mut drift = zeros(d);
+    set(&mut drift, d, 1, 1, 0.5, 0.0);
+    set(&mut drift, d, 2, 2, 1.5, 0.0);
+
+    // Ladder operator a with √n matrix elements
+    let mut x = zeros(d);
+    let mut y = zeros(d);
+    for n in 0..d - 1 {
+        let g = ((n + 1) as f64).sqrt() / 2.0;
+        set(&mut x, d, n, n + 1, g, 0.0);
+        set(&mut x, d, n + 1, n, g, 0.0);
+        set(&mut y, d, n, n + 1, 0.0, -g);
+        set(&mut y, d, n + 1, n, 0.0, g);
+    }
+    System {
+        name: "qutrit",
+        dim: d,
+        n_controls: 2,
+        drift,
+        controls: [x, y].concat(),
+        psi0: basis(d, 0),
+        target: basis(d, 2),
+        n_steps: 60,
+        dt: 0.05,
+    }
+}
+
+/// Two qubits with ZZ coupling and an x drive on each,
+/// |00⟩ → (|00⟩ + |11⟩)/√2 (a Bell state needs the coupling)
+fn coupled_qubits() -> System {
+    let (z, x, id) = (pauli_z(), pauli_x(), identity(2));
+    let (omega1, omega2, coupling) = (1.0, 1.3, 1.0);
+    let z1 = kron(&z, 2, &id, 2);
+    let z2 = kron(&id, 2, &z, 2);
+    let zz = kron(&z, 2, &z, 2);
+    let drift = combine(
+        1.0,
+        &combine(omega1 / 2.0, &z1, omega2 / 2.0, &z2),
+        coupling / 4.0,
+        &zz,
+    );
+    let x1 = scaled(0.5, &kron(&x, 2, &id, 2));
+    let x2 = scaled(0.5, &kron(&id, 2, &x, 2));
+
+    let s = std::f64::consts::FRAC_1_SQRT_2;
+    let mut bell = vec![0.0; 8];
+    bell[0] = s;
+    bell[6] = s;
+    System {
+        name: "coupled qubits",
+        dim: 4,
+        n_controls: 2,
+        drift,
+        controls: [x1, x2].concat(),
+        psi0: basis(4, 0),
+        target: bell,
+        n_steps: 80,
+        dt: 0.05,
+    }
+}
+
+/// Kerr oscillator truncated to 6 levels, H₀ = (K/2) a†a†aa,
+/// driven by the two quadratures (a + a†)/2 and i(a† - a)/2: |0⟩ → |1⟩
+fn kerr_oscillator() -> System {
+    let d = 6;
+    let kerr = 2.0;
+    let mut drift = zeros(d);
+    for n in 0..d {
+        set(&mut drift, d, n, n, kerr / 2.0 * (n * n - n) as f64, 0.0);
+    }
+    let mut x = zeros(d);
+    let mut p = zeros(d);
+    for n in 0..d - 1 {
+        let g = ((n + 1) as f64).sqrt() / 2.0;
+        set(&mut x, d, n, n + 1, g, 0.0);
+        set(&mut x, d, n + 1, n, g, 0.0);
+        set(&mut p, d, n, n + 1, 0.0, -g);
+        set(&mut p, d, n + 1, n, 0.0, g);
+    }
+    System {
+        name: "Kerr oscillator",
+        dim: d,
+        n_controls: 2,
+        drift,
+        controls: [x, p].concat(),
+        psi0: basis(d, 0),
+        target: basis(d, 1),
+        n_steps: 60,
+        dt: 0.05,
+    }
+}
+
+/// Example 14's closed-form qubit step (std math, for cross-checking)
+fn exact_qubit_step(state: &mut [f64], omega: f64, dt: f64) {
+    let omega0 = 1.0;
+    let omega_eff = (omega0 * omega0 + omega * omega).sqrt();
+    let theta = omega_eff * dt;
+    let nx = omega / omega_eff;
+    let nz = omega0 / omega_eff;
+    let c = (theta / 2.0).cos();
+    let s = (theta / 2.0).sin();
+    let (re0, im0, re1, im1) = (state[0], state[1], state[2], state[3]);
+    state[0] = c * re0 + s * nz * im0 + s * nx * im1;
+    state[1] = c * im0 - s * nz * re0 - s * nx * re1;
+    state[2] = c * re1 - s * nz * im1 + s * nx * im0;
+    state[3] = c * im1 + s * nz * re1 - s * nx * re0;
+}
+
+/// Adam on the control amplitudes; returns the final infidelity
+fn optimize(sys: &System, amps: &mut [f64], n_iters: usize, lr: f64) -> f64 {
+    let (beta1, beta2, epsilon) = (0.9, 0.999, 1e-8);
+    let mut m = vec![0.0; amps.len()];
+    let mut v = vec![0.0; amps.len()];
+    for iter in 0..n_iters {
+        let mut grad = vec![0.0; amps.len()];
+        let infid = d_infidelity(amps, &mut grad, sys, 1.0);
+        for i in 0..amps.len() {
+            m[i] = beta1 * m[i] + (1.0 - beta1) * grad[i];
+            v[i] = beta2 * v[i] + (1.0 - beta2) * grad[i] * grad[i];
+            let m_hat = m[i] / (1.0 - f64::powi(beta1, iter as i32 + 1));
+            let v_hat = v[i] / (1.0 - f64::powi(beta2, iter as i32 + 1));
+            amps[i] -= lr * m_hat / (v_hat.sqrt() + epsilon);
+        }
+        if iter % 100 == 0 {
+            println!("   iter {iter:4}: fidelity = {:.8}", 1.0 - infid);
+        }
+    }
+    infidelity(amps, sys)
+}
+
+/// Deterministic pseudo-random numbers in [0, 1) (64-bit LCG)
+fn next_uniform(state: &mut u64) -> f64 {
+    *state = state
+        .wrapping_mul(6364136223846793005)
+        .wrapping_add(1442695040888963407);
+    (*state >> 11) as f64 / (1u64 << 53) as f64
+}
+
+fn main() {
+    println!("N-Level Quantum System Simulator");
+    println!("================================\n");
+    println!("H(t) = H₀ + Σⱼ uⱼ(t) Hⱼ, piecewise-constant uⱼ, exact step propagator\n");
+
+    let mut rng = 11;
+
+    // 1. The general propagator reproduces example 14's closed form
+    println!("1. Cross-check against the closed-form qubit propagator");
+    let sys = qubit();
+    let amps: Vec<f64> = (0..sys.n_steps)
+        .map(|_| 6.0 * (2.0 * next_uniform(&mut rng) - 1.0))
+        .collect();
+    let psi = final_state(&amps, &sys);
+    let mut exact = sys.psi0.clone();
+    for &a in &amps {
+        exact_qubit_step(&mut exact, a, sys.dt);
+    }
+    let prop_err = psi
+        .iter()
+        .zip(&exact)
+        .map(|(a, b)| (a - b).abs())
+        .fold(0.0, f64::max);
+    println!("   max |ψ_taylor - ψ_closed_form| = {prop_err:.2e}\n");
+
+    // 2. Gradient w.r.t. every control amplitude vs finite differences
+    println!("2. Gradient check on the coupled-qubit system");
+    let sys = coupled_qubits();
+    let amps: Vec<f64> = (0..sys.n_steps * sys.n_controls)
+        .map(|_| 2.0 * next_uniform(&mut rng) - 1.0)
+        .collect();
+    let mut grad = vec![0.0; amps.len()];
+    let _ = d_infidelity(&amps, &mut grad, &sys, 1.0);
+    let h = 1e-6;
+    let mut grad_err: f64 = 0.0;
+    for i in 0..amps.len() {
+        let mut plus = amps.clone();
+        let mut minus = amps.clone();
+        plus[i] += h;
+        minus[i] -= h;
+        let fd = (infidelity(&plus, &sys) - infidelity(&minus, &sys)) / (2.0 * h);
+        grad_err = grad_err.max((fd - grad[i]).abs());
+    }
+    println!(
+        "   {} amplitudes, max |∇ - FD| = {grad_err:.2e}\n",
+        amps.len()
+    );
+
+    // 3. One optimization loop for every system
+    println!("3. Optimization with Adam (same loop for every system)");
+    let mut results = Vec::new();
+    for sys in [qubit(), qutrit(), coupled_qubits(), kerr_oscillator()] {
+        println!(
+            "\n   {} (d = {}, {} controls, T = {})",
+            sys.name,
+            sys.dim,
+            sys.n_controls,
+            sys.n_steps as f64 * sys.dt
+        );
+        let mut amps: Vec<f64> = (0..sys.n_steps * sys.n_controls)
+            .map(|_| 1.0 + 0.1 * (2.0 * next_uniform(&mut rng) - 1.0))
+            .collect();
+        let infid = optimize(&sys, &mut amps, 500, 0.1);
+        println!("   final fidelity = {:.8}", 1.0 - infid);
+
+        let psi = final_state(&amps, &sys);
+        let populations: Vec<String> = (0..sys.dim)
+            .map(|i| format!("{:.4}", psi[2 * i].powi(2) + psi[2 * i + 1].powi(2)))
+            .collect();
+        println!("   final populations = [{}]", populations.join(", "));
+        results.push((sys.name, 1.0 - infid));
+    }
+
+    println!("\n--- Verification ---");
+    if prop_err < 1e-10 {
+        println!("PASS: propagator matches the closed-form qubit rotation");
+    } else {
+        println!("FAIL: propagator error {prop_err:.2e}");
+    }
+    if grad_err < 1e-6 {
+        println!("PASS: gradients match finite differences");
+    } else {
+        println!("FAIL: gradient error {grad_err:.2e}");
+    }
+    for (name, fidelity) in results {
+        let status = if fidelity > 0.999 { "PASS" } else { "WEAK" };
+        println!("{status}: {name} fidelity {fidelity:.6}");
+    }
+}
@@ -0,0 +1,282 @@
+//! Example 19: Unitary Gate Fidelity for Gate Synthesis
+//!
+//! Example 14 optimizes the state transfer |0⟩ → |1⟩, which any pulse with the
+//! right area achieves, whatever it does to |1⟩ or to relative phases. To
+//! synthesize a gate, propagate the full unitary U and maximize
+//!
+//! F = |Tr(U_target† U)|² / d²
+//!
+//! which is 1 exactly when U equals the target up to a global phase.
+//!
+//! Model: example 14's qubit with x and y control quadratures
+//! H(t) = ω₀σz/2 + uₓ(t)σx/2 + u_y(t)σy/2
+//!
+//! Run with: RUSTFLAGS="-Z autodiff=Enable" cargo +enzyme run -p gate_fidelity
+
+#![feature(autodiff)]
+
+use std::autodiff::autodiff_reverse;
+
+const N_STEPS: usize = 100;
+const DT: f64 = 1.0 / N_STEPS as f64;
+const N_ITERS: usize = 500;
+const OMEGA0: f64 = 1.0; // Drift frequency
+const DIM: f64 = 2.0;
+
+/// Taylor series sin(x) - high precision
+fn my_sin(x: f64) -> f64 {
+    let mut sum = x;
+    let mut term = x;
+    let mut k = 1;
+    while k < 30 {
+        term *= -x * x / ((2 * k) as f64 * (2 * k + 1) as f64);
+        sum += term;
+        k += 1;
+    }
+    sum
+}
+
+/// Taylor series cos(x) - high precision
+fn my_cos(x: f64) -> f64 {
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    let mut k = 1;
+    while k < 30 {
+        term *= -x * x / ((2 * k - 1) as f64 * (2 * k) as f64);
+        sum += term;
+        k += 1;
+    }
+    sum
+}
+
+/// Newton-Raphson sqrt with range reduction into [0.25, 1]
+fn my_sqrt(x: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    let mut m = x;
+    let mut scale = 1.0;
+    while m > 1.0 {
+        m /= 4.0;
+        scale *= 2.0;
+    }
+    while m < 0.25 {
+        m *= 4.0;
+        scale /= 2.0;
+    }
+    let mut guess = 1.0;
+    let mut i = 0;
+    while i < 8 {
+        guess = (guess + m / guess) / 2.0;
+        i += 1;
+    }
+    guess * scale
+}
+
+/// 2×2 complex matrix, row-major, (re, im) interleaved:
+/// [Re u₀₀, Im u₀₀, Re u₀₁, Im u₀₁, Re u₁₀, Im u₁₀, Re u₁₁, Im u₁₁]
+type Unitary = [f64; 8];
+
+const IDENTITY: Unitary = [1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0];
+
+/// Exact step U ← exp(-i(n·σ)θ/2) U with θ = |ω|Δt, ω = (uₓ, u_y, ω₀)
+/// exp(-i(n·σ)θ/2) = cos(θ/2)I - i sin(θ/2)(nₓσx + n_yσy + n_zσz)
+fn apply_exact_step(u: &mut Unitary, ux: f64, uy: f64) {
+    let omega_eff = my_sqrt(OMEGA0 * OMEGA0 + ux * ux + uy * uy);
+    let theta = omega_eff * DT;
+    let nx = ux / omega_eff;
+    let ny = uy / omega_eff;
+    let nz = OMEGA0 / omega_eff;
+
+    let c = my_cos(theta / 2.0);
+    let s = my_sin(theta / 2.0);
+
+    // Step matrix S
+    // S₀₀ = c - i s n_z        S₀₁ = -s n_y - i s nₓ
+    // S₁₀ = s n_y - i s nₓ     S₁₁ = c + i s n_z
+    let (s00_re, s00_im) = (c, -s * nz);
+    let (s01_re, s01_im) = (-s * ny, -s * nx);
+    let (s10_re, s10_im) = (s * ny, -s * nx);
+    let (s11_re, s11_im) = (c, s * nz);
+
+    // U ← S U, one column at a time
+    let mut col = 0;
+    while col < 2 {
+        let (a_re, a_im) = (u[2 * col], u[2 * col + 1]);
+        let (b_re, b_im) = (u[4 + 2 * col], u[4 + 2 * col + 1]);
+        u[2 * col] = s00_re * a_re - s00_im * a_im + s01_re * b_re - s01_im * b_im;
+        u[2 * col + 1] = s00_re * a_im + s00_im * a_re + s01_re * b_im + s01_im * b_re;
+        u[4 + 2 * col] = s10_re * a_re - s10_im * a_im + s11_re * b_re - s11_im * b_im;
+        u[4 + 2 * col + 1] = s10_re * a_im + s10_im * a_re + s11_re * b_im + s11_im * b_re;
+        col += 1;
+    }
+}
+
+/// Full propagator U(T) for controls laid out as [uₓ₀, u_y₀, uₓ₁, u_y₁, ...]
+fn propagate(controls: &[f64]) -> Unitary {
+    let mut u = IDENTITY;
+    let mut k = 0;
+    while k < N_STEPS {
+        apply_exact_step(&mut u, controls[2 * k], controls[2 * k + 1]);
+        k += 1;
+    }
+    u
+}
+
+/// |Tr(A† B)|² for 2×2 complex matrices
+fn trace_overlap_sq(a: &[f64], b: &[f64]) -> f64 {
+    // Tr(A† B) = Σᵢⱼ conj(Aᵢⱼ) Bᵢⱼ
+    let mut tr_re = 0.0;
+    let mut tr_im = 0.0;
+    let mut e = 0;
+    while e < 4 {
+        let (a_re, a_im) = (a[2 * e], a[2 * e + 1]);
+        let (b_re, b_im) = (b[2 * e], b[2 * e + 1]);
+        tr_re += a_re * b_re + a_im * b_im;
+        tr_im += a_re * b_im - a_im * b_re;
+        e += 1;
+    }
+    tr_re * tr_re + tr_im * tr_im
+}
+
+/// Gate infidelity: 1 - |Tr(U_target† U)|² / d²
+#[autodiff_reverse(d_gate_infidelity, Duplicated, Const, Active)]
+fn gate_infidelity(controls: &[f64], target: &[f64]) -> f64 {
+    let u = propagate(controls);
+    1.0 - trace_overlap_sq(target, &u) / (DIM * DIM)
+}
+
+/// Example 14's objective: 1 - |⟨1|U|0⟩|²
+#[autodiff_reverse(d_state_infidelity, Duplicated, Active)]
+fn state_infidelity(controls: &[f64]) -> f64 {
+    let u = propagate(controls);
+    1.0 - (u[4] * u[4] + u[5] * u[5])
+}
+
+/// Average gate fidelity over all pure input states: (d·F + 1) / (d + 1)
+fn average_gate_fidelity(gate_fidelity: f64) -> f64 {
+    (DIM * gate_fidelity + 1.0) / (DIM + 1.0)
+}
+
+/// R_n(θ) = cos(θ/2)I - i sin(θ/2)(n·σ) for a unit axis n
+fn rotation(axis: [f64; 3], angle: f64) -> Unitary {
+    let (c, s) = ((angle / 2.0).cos(), (angle / 2.0).sin());
+    let [nx, ny, nz] = axis;
+    [c, -s * nz, -s * ny, -s * nx, s * ny, -s * nx, c, s * nz]
+}
+
+fn targets() -> Vec<(&'static str, Unitary)> {
+    let s = std::f64::consts::FRAC_1_SQRT_2;
+    let pi = std::f64::consts::PI;
+    let n = 1.0 / 3.0_f64.sqrt();
+    vec![
+        ("X", [0.0, 0.0, 1.0, 0.0, 1.0, 0.0, 0.0, 0.0]),
+        ("Hadamard", [s, 0.0, s, 0.0, s, 0.0, -s, 0.0]),
+        // √X = ((1+i)/2, (1-i)/2; (1-i)/2, (1+i)/2)
+        ("√X", [0.5, 0.5, 0.5, -0.5, 0.5, -0.5, 0.5, 0.5]),
+        ("R[1,1,1](2π/3)", rotation([n, n, n], 2.0 * pi / 3.0)),
+        ("R[0.6,0,0.8](1.3)", rotation([0.6, 0.0, 0.8], 1.3)),
+    ]
+}
+
+/// Adam on the controls for any objective's reverse-mode gradient. Returns the
+/// loss at the final controls, after the last update.
+fn optimize(controls: &mut [f64], grad_fn: &dyn Fn(&[f64], &mut [f64]) -> f64, lr: f64) -> f64 {
+    let (beta1, beta2, epsilon) = (0.9, 0.999, 1e-8);
+    let mut m = vec![0.0; controls.len()];
+    let mut v = vec![0.0; controls.len()];
+    let mut grad = vec![0.0; controls.len()];
+    for iter in 0..N_ITERS {
+        grad.fill(0.0);
+        grad_fn(controls, &mut grad);
+        for i in 0..controls.len() {
+            m[i] = beta1 * m[i] + (1.0 - beta1) * grad[i];
+            v[i] = beta2 * v[i] + (1.0 - beta2) * grad[i] * grad[i];
+            let m_hat = m[i] / (1.0 - f64::powi(beta1, iter as i32 + 1));
+            let v_hat = v[i] / (1.0 - f64::powi(beta2, iter as i32 + 1));
+            controls[i] -= lr * m_hat / (v_hat.sqrt() + epsilon);
+        }
+    }
+    grad.fill(0.0);
+    grad_fn(controls, &mut grad)
+}
+
+/// Deterministic pseudo-random numbers in [0, 1) (64-bit LCG)
+fn next_uniform(state: &mut u64) -> f64 {
+    *state = state
+        .wrapping_mul(6364136223846793005)
+        .wrapping_add(1442695040888963407);
+    (*state >> 11) as f64 / (1u64 << 53) as f64
+}
+
+fn initial_controls(rng: &mut u64) -> Vec<f64> {
+    (0..2 * N_STEPS)
+        .map(|_| 2.0 * (2.0 * next_uniform(rng) - 1.0))
+        .collect()
+}
+
+fn main() {
+    println!("Unitary Gate Fidelity");
+    println!("=====================\n");
+    println!("Objective: F = |Tr(U_target† U)|² / d², from the full propagator U(T)");
+    println!("Controls: uₓ and u_y, {N_STEPS} steps, T = 1\n");
+
+    let mut rng = 5;
+    let x_gate = targets()[0].1;
+
+    // 1. State transfer does not pin down the gate
+    println!("1. Optimizing example 14's state-transfer objective |0⟩ → |1⟩");
+    let mut controls = initial_controls(&mut rng);
+    let state_infid = optimize(&mut controls, &|c, g| d_state_infidelity(c, g, 1.0), 0.1);
+    let x_fidelity = 1.0 - gate_infidelity(&controls, &x_gate);
+    println!("   state fidelity   = {:.8}", 1.0 - state_infid);
+    println!("   X gate fidelity  = {x_fidelity:.8} (the relative phase is unconstrained)\n");
+
+    // 2. Gradient check
+    println!("2. Gradient check (autodiff vs finite differences)");
+    let controls = initial_controls(&mut rng);
+    let mut grad = vec![0.0; controls.len()];
+    let _ = d_gate_infidelity(&controls, &mut grad, &x_gate, 1.0);
+    let h = 1e-6;
+    let mut grad_err: f64 = 0.0;
+    for i in 0..controls.len() {
+        let mut plus = controls.clone();
+        let mut minus = controls.clone();
+        plus[i] += h;
+        minus[i] -= h;
+        let fd = (gate_infidelity(&plus, &x_gate) - gate_infidelity(&minus, &x_gate)) / (2.0 * h);
+        grad_err = grad_err.max((fd - grad[i]).abs());
+    }
+    println!("   max |∇ - FD| = {grad_err:.2e}\n");
+
+    // 3. Gate synthesis
+    println!("3. Gate synthesis with Adam ({N_ITERS} iterations)");
+    println!("   {:<20} {:>14} {:>14}", "target", "F", "F_avg");
+    let mut worst_fidelity: f64 = 1.0;
+    for (name, target) in targets() {
+        let mut controls = initial_controls(&mut rng);
+        let infid = optimize(
+            &mut controls,
+            &|c, g| d_gate_infidelity(c, g, &target, 1.0),
+            0.1,
+        );
+        let fidelity = 1.0 - infid;
+        worst_fidelity = worst_fidelity.min(fidelity);
+        println!(
+            "   {name:<20} {fidelity:>14.10} {:>14.10}",
+            average_gate_fidelity(fidelity)
+        );
+    }
+
+    println!("\n--- Verification ---");
+    if grad_err < 1e-6 {
+        println!("PASS: gate fidelity gradients match finite differences");
+    } else {
+        println!("FAIL: gradient error {grad_err:.2e}");
+    }
+    if worst_fidelity > 0.9999 {
+        println!("SUCCESS: every target synthesized with >99.99% gate fidelity");
+    } else {
+        println!("Need more optimization iterations (worst F = {worst_fidelity:.6})");
+    }
+}
@@ -0,0 +1,404 @@
+//! Example 20: Two-Qubit Entangling Gate Optimization
+//!
+//! Two qubits with their own drift, a fixed coupling, and x/y controls on each:
+//!
+//! H(t) = ω₁Z₁/2 + ω₂Z₂/2 + H_coupling + Σ_q [uₓ,q(t) X_q/2 + u_y,q(t) Y_q/2]
+//!
+//! with H_coupling = J·Z₁Z₂/4 (ZZ) or J·(X₁X₂ + Y₁Y₂)/4 (XY, exchange).
+//!
+//! There is no closed form for a 4×4 step, so each step builds
+//! exp(-iHΔt) with a Taylor series plus scaling and squaring, written in
+//! Enzyme-friendly loops. Adam on the reverse-mode gradient of the gate
+//! fidelity then synthesizes CNOT and CZ.
+//!
+//! Run with: RUSTFLAGS="-Z autodiff=Enable" cargo +enzyme run -p two_qubit_gates
+
+#![feature(autodiff)]
+
+use std::autodiff::autodiff_reverse;
+
+const DIM: usize = 4;
+const N_STEPS: usize = 80;
+const DT: f64 = 0.05;
+const N_CONTROLS: usize = 4; // uₓ,₁ u_y,₁ uₓ,₂ u_y,₂
+const N_ITERS: usize = 500;
+/// Taylor order for exp(B) with ‖B‖ ≤ 1/2: truncation error < 1e-20
+const TAYLOR_ORDER: usize = 16;
+
+/// Complex 4×4 matrix, row-major, (re, im) interleaved:
+/// element (i, j) at 2·(4i + j)
+type Mat = [f64; 2 * DIM * DIM];
+
+/// Two-qubit model: drift (including coupling) and one Hamiltonian per control
+struct Model {
+    drift: Mat,
+    controls: [Mat; N_CONTROLS],
+}
+
+/// C = A B for complex 4×4 matrices
+fn mat_mul(a: &Mat, b: &Mat) -> Mat {
+    let mut c = [0.0; 2 * DIM * DIM];
+    let mut i = 0;
+    while i < DIM {
+        let mut j = 0;
+        while j < DIM {
+            let mut re = 0.0;
+            let mut im = 0.0;
+            let mut k = 0;
+            while k < DIM {
+                let (a_re, a_im) = (a[2 * (i * DIM + k)], a[2 * (i * DIM + k) + 1]);
+                let (b_re, b_im) = (b[2 * (k * DIM + j)], b[2 * (k * DIM + j) + 1]);
+                re += a_re * b_re - a_im * b_im;
+                im += a_re * b_im + a_im * b_re;
+                k += 1;
+            }
+            c[2 * (i * DIM + j)] = re;
+            c[2 * (i * DIM + j) + 1] = im;
+            j += 1;
+        }
+        i += 1;
+    }
+    c
+}
+
+/// Matrix exponential exp(A) by scaling and squaring
+///
+/// 1. Halve A until its max row sum (of |re| + |im|) is at most 1/2
+/// 2. Sum the Taylor series of exp(A/2ˢ)
+/// 3. Square the result s times
+fn expm(a: &Mat) -> Mat {
+    let mut norm = 0.0;
+    let mut i = 0;
+    while i < DIM {
+        let mut row = 0.0;
+        let mut j = 0;
+        while j < DIM {
+            let re = a[2 * (i * DIM + j)];
+            let im = a[2 * (i * DIM + j) + 1];
+            row += (if re < 0.0 { -re } else { re }) + (if im < 0.0 { -im } else { im });
+            j += 1;
+        }
+        if row > norm {
+            norm = row;
+        }
+        i += 1;
+    }
+
+    let mut scale = 1.0;
+    let mut squarings = 0;
+    while norm * scale > 0.5 {
+        scale /= 2.0;
+        squarings += 1;
+    }
+
+    let mut b = [0.0; 2 * DIM * DIM];
+    let mut e = 0;
+    while e < 2 * DIM * DIM {
+        b[e] = a[e] * scale;
+        e += 1;
+    }
+
+    // Σₙ Bⁿ/n!
+    let mut term = [0.0; 2 * DIM * DIM];
+    let mut acc = [0.0; 2 * DIM * DIM];
+    let mut i = 0;
+    while i < DIM {
+        term[2 * (i * DIM + i)] = 1.0;
+        acc[2 * (i * DIM + i)] = 1.0;
+        i += 1;
+    }
+    let mut n = 1;
+    while n <= TAYLOR_ORDER {
+        let next = mat_mul(&term, &b);
+        let mut e = 0;
+        while e < 2 * DIM * DIM {
+            term[e] = next[e] / n as f64;
+            acc[e] += term[e];
+            e += 1;
+        }
+        n += 1;
+    }
+
+    let mut s = 0;
+    while s < squarings {
+        acc = mat_mul(&acc, &acc);
+        s += 1;
+    }
+    acc
+}
+
+/// U ← exp(-iHΔt) U with H = H₀ + Σⱼ uⱼ Hⱼ
+fn apply_step(u: &mut Mat, amps: &[f64], model: &Model) {
+    // A = -iHΔt: Re A = Im H·Δt, Im A = -Re H·Δt
+    let mut a = [0.0; 2 * DIM * DIM];
+    let mut e = 0;
+    while e < DIM * DIM {
+        let mut h_re = model.drift[2 * e];
+        let mut h_im = model.drift[2 * e + 1];
+        let mut j = 0;
+        while j < N_CONTROLS {
+            h_re += amps[j] * model.controls[j][2 * e];
+            h_im += amps[j] * model.controls[j][2 * e + 1];
+            j += 1;
+        }
+        a[2 * e] = h_im * DT;
+        a[2 * e + 1] = -h_re * DT;
+        e += 1;
+    }
+    let step = expm(&a);
+    *u = mat_mul(&step, u);
+}
+
+/// Full propagator U(T); amplitudes for step k start at k·N_CONTROLS
+fn propagate(amps: &[f64], model: &Model) -> Mat {
+    let mut u = [0.0; 2 * DIM * DIM];
+    let mut i = 0;
+    while i < DIM {
+        u[2 * (i * DIM + i)] = 1.0;
+        i += 1;
+    }
+    let mut k = 0;
+    while k < N_STEPS {
+        apply_step(&mut u, &amps[k * N_CONTROLS..(k + 1) * N_CONTROLS], model);
+        k += 1;
+    }
+    u
+}
+
+/// Gate infidelity: 1 - |Tr(U_target† U)|² / d²
+#[autodiff_reverse(d_gate_infidelity, Duplicated, Const, Const, Active)]
+fn gate_infidelity(amps: &[f64], model: &Model, target: &[f64]) -> f64 {
+    let u = propagate(amps, model);
+    let mut tr_re = 0.0;
+    let mut tr_im = 0.0;
+    let mut e = 0;
+    while e < DIM * DIM {
+        let (t_re, t_im) = (target[2 * e], target[2 * e + 1]);
+        let (u_re, u_im) = (u[2 * e], u[2 * e + 1]);
+        tr_re += t_re * u_re + t_im * u_im;
+        tr_im += t_re * u_im - t_im * u_re;
+        e += 1;
+    }
+    let d = DIM as f64;
+    1.0 - (tr_re * tr_re + tr_im * tr_im) / (d * d)
+}
+
+// ---------------------------------------------------------------------------
+// Building the model (plain Rust, not differentiated)
+// ---------------------------------------------------------------------------
+
+/// Single-qubit operators as 2×2 complex matrices
+const I2: [f64; 8] = [1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0];
+const X: [f64; 8] = [0.0, 0.0, 1.0, 0.0, 1.0, 0.0, 0.0, 0.0];
+const Y: [f64; 8] = [0.0, 0.0, 0.0, -1.0, 0.0, 1.0, 0.0, 0.0];
+const Z: [f64; 8] = [1.0, 0.0, 0.0, 0.0, 0.0, 0.0, -1.0, 0.0];
+
+/// A ⊗ B for 2×2 complex matrices
+fn kron(a: &[f64; 8], b: &[f64; 8]) -> Mat {
+    let mut out = [0.0; 2 * DIM * DIM];
+    for i in 0..2 {
+        for j in 0..2 {
+            let (a_re, a_im) = (a[2 * (2 * i + j)], a[2 * (2 * i + j) + 1]);
+            for k in 0..2 {
+                for l in 0..2 {
+                    let (b_re, b_im) = (b[2 * (2 * k + l)], b[2 * (2 * k + l) + 1]);
+                    let e = (2 * i + k) * DIM + (2 * j + l);
+                    out[2 * e] = a_re * b_re - a_im * b_im;
+                    out[2 * e + 1] = a_re * b_im + a_im * b_re;
+                }
+            }
+        }
+    }
+    out
+}
+
+/// Σ cᵢ Mᵢ with real coefficients
+fn lin_comb(terms: &[(f64, Mat)]) -> Mat {
+    let mut out = [0.0; 2 * DIM * DIM];
+    for (c, m) in terms {
+        for e in 0..2 * DIM * DIM {
+            out[e] += c * m[e];
+        }
+    }
+    out
+}
+
+#[derive(Clone, Copy)]
+enum Coupling {
+    Zz,
+    Xy,
+}
+
+fn build_model(coupling: Coupling) -> Model {
+    let (omega1, omega2, j) = (1.0, 1.4, 1.0);
+    let coupling_term = match coupling {
+        Coupling::Zz => lin_comb(&[(j / 4.0, kron(&Z, &Z))]),
+        Coupling::Xy => lin_comb(&[(j / 4.0, kron(&X, &X)), (j / 4.0, kron(&Y, &Y))]),
+    };
+    Model {
+        drift: lin_comb(&[
+            (omega1 / 2.0, kron(&Z, &I2)),
+            (omega2 / 2.0, kron(&I2, &Z)),
+            (1.0, coupling_term),
+        ]),
+        controls: [
+            lin_comb(&[(0.5, kron(&X, &I2))]),
+            lin_comb(&[(0.5, kron(&Y, &I2))]),
+            lin_comb(&[(0.5, kron(&I2, &X))]),
+            lin_comb(&[(0.5, kron(&I2, &Y))]),
+        ],
+    }
+}
+
+/// Real 4×4 matrix into the complex layout
+fn real_matrix(rows: [[f64; 4]; 4]) -> Mat {
+    let mut out = [0.0; 2 * DIM * DIM];
+    for i in 0..DIM {
+        for j in 0..DIM {
+            out[2 * (i * DIM + j)] = rows[i][j];
+        }
+    }
+    out
+}
+
+/// CNOT with qubit 1 as control, basis order |q₁q₂⟩ = 00, 01, 10, 11
+fn cnot() -> Mat {
+    real_matrix([
+        [1.0, 0.0, 0.0, 0.0],
+        [0.0, 1.0, 0.0, 0.0],
+        [0.0, 0.0, 0.0, 1.0],
+        [0.0, 0.0, 1.0, 0.0],
+    ])
+}
+
+fn cz() -> Mat {
+    real_matrix([
+        [1.0, 0.0, 0.0, 0.0],
+        [0.0, 1.0, 0.0, 0.0],
+        [0.0, 0.0, 1.0, 0.0],
+        [0.0, 0.0, 0.0, -1.0],
+    ])
+}
+
+/// Single-qubit rotation exp(-iθ(n·σ)/2) (std math, for the expm check)
+fn rotation(axis: [f64; 3], angle: f64) -> [f64; 8] {
+    let (c, s) = ((angle / 2.0).cos(), (angle / 2.0).sin());
+    let [nx, ny, nz] = axis;
+    [c, -s * nz, -s * ny, -s * nx, s * ny, -s * nx, c, s * nz]
+}
+
+/// Deterministic pseudo-random numbers in [0, 1) (64-bit LCG)
+fn next_uniform(state: &mut u64) -> f64 {
+    *state = state
+        .wrapping_mul(6364136223846793005)
+        .wrapping_add(1442695040888963407);
+    (*state >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Adam on the amplitudes; returns the final gate fidelity
+fn optimize(model: &Model, target: &Mat, rng: &mut u64) -> f64 {
+    let n = N_STEPS * N_CONTROLS;
+    let mut amps: Vec<f64> = (0..n).map(|_| 2.0 * next_uniform(rng) - 1.0).collect();
+    let (beta1, beta2, epsilon, lr) = (0.9, 0.999, 1e-8, 0.05);
+    let mut m = vec![0.0; n];
+    let mut v = vec![0.0; n];
+    for iter in 0..N_ITERS {
+        let mut grad = vec![0.0; n];
+        let infid = d_gate_infidelity(&amps, &mut grad, model, target, 1.0);
+        for i in 0..n {
+            m[i] = beta1 * m[i] + (1.0 - beta1) * grad[i];
+            v[i] = beta2 * v[i] + (1.0 - beta2) * grad[i] * grad[i];
+            let m_hat = m[i] / (1.0 - f64::powi(beta1, iter as i32 + 1));
+            let v_hat = v[i] / (1.0 - f64::powi(beta2, iter as i32 + 1));
+            amps[i] -= lr * m_hat / (v_hat.sqrt() + epsilon);
+        }
+        if iter % 100 == 0 {
+            println!("      iter {iter:4}: fidelity = {:.8}", 1.0 - infid);
+        }
+    }
+    1.0 - gate_infidelity(&amps, model, target)
+}
+
+fn main() {
+    println!("Two-Qubit Entangling Gates");
+    println!("==========================\n");
+    println!("{N_STEPS} steps of Δt = {DT}, {N_CONTROLS} controls (x/y on each qubit)\n");
+
+    // 1. expm against a closed form: exp(-i(θ₁X₁ + θ₂Z₂)/2) = R_x(θ₁) ⊗ R_z(θ₂)
+    println!("1. Matrix exponential check");
+    let (theta1, theta2) = (2.3, -4.1);
+    let mut a = lin_comb(&[(theta1 / 2.0, kron(&X, &I2)), (theta2 / 2.0, kron(&I2, &Z))]);
+    // Multiply by -i: (re, im) → (im, -re)
+    for e in 0..DIM * DIM {
+        let (re, im) = (a[2 * e], a[2 * e + 1]);
+        a[2 * e] = im;
+        a[2 * e + 1] = -re;
+    }
+    let u = expm(&a);
+    let expected = kron(
+        &rotation([1.0, 0.0, 0.0], theta1),
+        &rotation([0.0, 0.0, 1.0], theta2),
+    );
+    let expm_err = u
+        .iter()
+        .zip(&expected)
+        .map(|(x, y)| (x - y).abs())
+        .fold(0.0, f64::max);
+    println!("   max |expm - R_x ⊗ R_z| = {expm_err:.2e}\n");
+
+    // 2. Gradient check
+    println!("2. Gradient check (autodiff vs finite differences)");
+    let mut rng = 3;
+    let model = build_model(Coupling::Zz);
+    let target = cnot();
+    let amps: Vec<f64> = (0..N_STEPS * N_CONTROLS)
+        .map(|_| 2.0 * next_uniform(&mut rng) - 1.0)
+        .collect();
+    let mut grad = vec![0.0; amps.len()];
+    let _ = d_gate_infidelity(&amps, &mut grad, &model, &target, 1.0);
+    let h = 1e-6;
+    let mut grad_err: f64 = 0.0;
+    for i in (0..amps.len()).step_by(7) {
+        let mut plus = amps.clone();
+        let mut minus = amps.clone();
+        plus[i] += h;
+        minus[i] -= h;
+        let fd = (gate_infidelity(&plus, &model, &target)
+            - gate_infidelity(&minus, &model, &target))
+            / (2.0 * h);
+        grad_err = grad_err.max((fd - grad[i]).abs());
+    }
+    println!("   max |∇ - FD| (every 7th amplitude) = {grad_err:.2e}\n");
+
+    // 3. Gate synthesis
+    println!("3. Gate synthesis with Adam ({N_ITERS} iterations)");
+    let mut results = Vec::new();
+    for (coupling_name, coupling) in [("ZZ", Coupling::Zz), ("XY", Coupling::Xy)] {
+        let model = build_model(coupling);
+        for (gate_name, target) in [("CNOT", cnot()), ("CZ", cz())] {
+            println!("   {gate_name} with {coupling_name} coupling:");
+            let fidelity = optimize(&model, &target, &mut rng);
+            println!("      final fidelity = {fidelity:.8}");
+            results.push((gate_name, coupling_name, fidelity));
+        }
+    }
+
+    println!("\n--- Verification ---");
+    if expm_err < 1e-12 {
+        println!("PASS: matrix exponential matches the closed form");
+    } else {
+        println!("FAIL: expm error {expm_err:.2e}");
+    }
+    if grad_err < 1e-6 {
+        println!("PASS: gradients match finite differences");
+    } else {
+        println!("FAIL: gradient error {grad_err:.2e}");
+    }
+    for (gate, coupling, fidelity) in results {
+        if fidelity > 0.999 {
+            println!("SUCCESS: {gate} ({coupling}) fidelity {fidelity:.6} > 99.9%");
+        } else {
+            println!("Need more iterations: {gate} ({coupling}) fidelity {fidelity:.6}");
+        }
+    }
+}
@@ -0,0 +1,477 @@
+//! Example 22: Smooth Pulse Parametrizations
+//!
+//! Examples 14 and 19 optimize one free amplitude per time step, and the
+//! optimized pulse is as jagged as its random starting point. Here the pulse
+//! is a function of a few shape parameters, evaluated onto the time grid
+//! inside the differentiated objective:
+//! - Fourier/CRAB: sine envelope times a few randomized harmonics
+//! - Gaussian: amplitude and width
+//! - DRAG: Gaussian on x plus β·du_x/dt on y
+//! - cubic B-spline: a handful of control points per quadrature
+//!
+//! Reverse mode carries the gradient from the gate fidelity back through the
+//! propagator and the pulse evaluation to the shape parameters.
+//!
+//! Model: example 19's qubit, H(t) = ω₀σz/2 + uₓ(t)σx/2 + u_y(t)σy/2, target X
+//!
+//! Run with: RUSTFLAGS="-Z autodiff=Enable" cargo +enzyme run -p pulse_shapes
+
+#![feature(autodiff)]
+
+use std::autodiff::autodiff_reverse;
+
+const N_STEPS: usize = 100;
+const DURATION: f64 = 1.0;
+const DT: f64 = DURATION / N_STEPS as f64;
+const N_ITERS: usize = 500;
+const OMEGA0: f64 = 1.0; // Drift frequency
+const DIM: f64 = 2.0;
+
+/// Series e^x: halve x until |x| ≤ 1/2, sum 20 terms, square back.
+/// ±inf return early, since halving never brings them below 1/2.
+fn my_exp(x: f64) -> f64 {
+    if x == f64::NEG_INFINITY {
+        return 0.0;
+    }
+    if x == f64::INFINITY {
+        return x;
+    }
+    let mut r = x;
+    let mut halvings = 0;
+    while (if r < 0.0 { -r } else { r }) > 0.5 {
+        r /= 2.0;
+        halvings += 1;
+    }
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    let mut k = 1;
+    while k < 20 {
+        term *= r / k as f64;
+        sum += term;
+        k += 1;
+    }
+    let mut i = 0;
+    while i < halvings {
+        sum *= sum;
+        i += 1;
+    }
+    sum
+}
+
+/// Taylor series sin(x) - high precision
+fn my_sin(x: f64) -> f64 {
+    let mut sum = x;
+    let mut term = x;
+    let mut k = 1;
+    while k < 30 {
+        term *= -x * x / ((2 * k) as f64 * (2 * k + 1) as f64);
+        sum += term;
+        k += 1;
+    }
+    sum
+}
+
+/// Taylor series cos(x) - high precision
+fn my_cos(x: f64) -> f64 {
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    let mut k = 1;
+    while k < 30 {
+        term *= -x * x / ((2 * k - 1) as f64 * (2 * k) as f64);
+        sum += term;
+        k += 1;
+    }
+    sum
+}
+
+/// Newton-Raphson sqrt with range reduction into [0.25, 1]
+fn my_sqrt(x: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    let mut m = x;
+    let mut scale = 1.0;
+    while m > 1.0 {
+        m /= 4.0;
+        scale *= 2.0;
+    }
+    while m < 0.25 {
+        m *= 4.0;
+        scale /= 2.0;
+    }
+    let mut guess = 1.0;
+    let mut i = 0;
+    while i < 8 {
+        guess = (guess + m / guess) / 2.0;
+        i += 1;
+    }
+    guess * scale
+}
+
+/// Midpoint of time step k
+fn step_time(k: usize) -> f64 {
+    (k as f64 + 0.5) * DT
+}
+
+/// How a parameter vector becomes uₓ(t) and u_y(t)
+enum Shape {
+    /// Linear in the parameters: u = Φp, with the design matrix Φ
+    /// [2·N_STEPS × n_params] precomputed (rows 0..N_STEPS are uₓ, the rest u_y)
+    Linear(Vec<f64>),
+    /// [A, σ]: uₓ = A·exp(-(t - T/2)²/2σ²), u_y = 0
+    Gaussian,
+    /// [A, σ, β]: the Gaussian on x plus u_y = β·duₓ/dt
+    Drag,
+}
+
+struct Basis {
+    name: &'static str,
+    n_params: usize,
+    shape: Shape,
+}
+
+/// Evaluates the pulse on the time grid
+fn pulse(params: &[f64], basis: &Basis, ux: &mut [f64; N_STEPS], uy: &mut [f64; N_STEPS]) {
+    match &basis.shape {
+        Shape::Linear(phi) => {
+            let mut k = 0;
+            while k < N_STEPS {
+                let mut x = 0.0;
+                let mut y = 0.0;
+                let mut j = 0;
+                while j < basis.n_params {
+                    x += phi[k * basis.n_params + j] * params[j];
+                    y += phi[(N_STEPS + k) * basis.n_params + j] * params[j];
+                    j += 1;
+                }
+                ux[k] = x;
+                uy[k] = y;
+                k += 1;
+            }
+        }
+        Shape::Gaussian | Shape::Drag => {
+            let amp = params[0];
+            let sigma = params[1];
+            let mut k = 0;
+            while k < N_STEPS {
+                let s = step_time(k) - DURATION / 2.0;
+                let g = amp * my_exp(-s * s / (2.0 * sigma * sigma));
+                ux[k] = g;
+                uy[k] = 0.0;
+                if let Shape::Drag = basis.shape {
+                    // d/dt [A·exp(-s²/2σ²)] = -(s/σ²)·A·exp(-s²/2σ²)
+                    uy[k] = -params[2] * s / (sigma * sigma) * g;
+                }
+                k += 1;
+            }
+        }
+    }
+}
+
+/// 2×2 complex matrix, row-major, (re, im) interleaved
+type Unitary = [f64; 8];
+
+const IDENTITY: Unitary = [1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0];
+
+/// Exact step U ← exp(-i(n·σ)θ/2) U with θ = |ω|Δt, ω = (uₓ, u_y, ω₀)
+fn apply_exact_step(u: &mut Unitary, ux: f64, uy: f64) {
+    let omega_eff = my_sqrt(OMEGA0 * OMEGA0 + ux * ux + uy * uy);
+    let theta = omega_eff * DT;
+    let nx = ux / omega_eff;
+    let ny = uy / omega_eff;
+    let nz = OMEGA0 / omega_eff;
+
+    let c = my_cos(theta / 2.0);
+    let s = my_sin(theta / 2.0);
+
+    let (s00_re, s00_im) = (c, -s * nz);
+    let (s01_re, s01_im) = (-s * ny, -s * nx);
+    let (s10_re, s10_im) = (s * ny, -s * nx);
+    let (s11_re, s11_im) = (c, s * nz);
+
+    let mut col = 0;
+    while col < 2 {
+        let (a_re, a_im) = (u[2 * col], u[2 * col + 1]);
+        let (b_re, b_im) = (u[4 + 2 * col], u[4 + 2 * col + 1]);
+        u[2 * col] = s00_re * a_re - s00_im * a_im + s01_re * b_re - s01_im * b_im;
+        u[2 * col + 1] = s00_re * a_im + s00_im * a_re + s01_re * b_im + s01_im * b_re;
+        u[4 + 2 * col] = s10_re * a_re - s10_im * a_im + s11_re * b_re - s11_im * b_im;
+        u[4 + 2 * col + 1] = s10_re * a_im + s10_im * a_re + s11_re * b_im + s11_im * b_re;
+        col += 1;
+    }
+}
+
+/// Gate infidelity of the shaped pulse: 1 - |Tr(U_target† U)|² / d²
+#[autodiff_reverse(d_gate_infidelity, Duplicated, Const, Const, Active)]
+fn gate_infidelity(params: &[f64], basis: &Basis, target: &[f64]) -> f64 {
+    let mut ux = [0.0; N_STEPS];
+    let mut uy = [0.0; N_STEPS];
+    pulse(params, basis, &mut ux, &mut uy);
+
+    let mut u = IDENTITY;
+    let mut k = 0;
+    while k < N_STEPS {
+        apply_exact_step(&mut u, ux[k], uy[k]);
+        k += 1;
+    }
+
+    // Tr(T† U) = Σᵢⱼ conj(Tᵢⱼ) Uᵢⱼ
+    let mut tr_re = 0.0;
+    let mut tr_im = 0.0;
+    let mut e = 0;
+    while e < 4 {
+        tr_re += target[2 * e] * u[2 * e] + target[2 * e + 1] * u[2 * e + 1];
+        tr_im += target[2 * e] * u[2 * e + 1] - target[2 * e + 1] * u[2 * e];
+        e += 1;
+    }
+    1.0 - (tr_re * tr_re + tr_im * tr_im) / (DIM * DIM)
+}
+
+// ---------------------------------------------------------------------------
+// Building the bases (plain Rust, not differentiated)
+// ---------------------------------------------------------------------------
+
+/// One free amplitude per step and quadrature, as in example 19
+fn piecewise_constant() -> Basis {
+    let n = 2 * N_STEPS;
+    let mut phi = vec![0.0; 2 * N_STEPS * n];
+    for i in 0..n {
+        phi[i * n + i] = 1.0;
+    }
+    Basis {
+        name: "piecewise constant",
+        n_params: n,
+        shape: Shape::Linear(phi),
+    }
+}
+
+/// CRAB: u(t) = sin(πt/T)·[a₀ + Σₙ (aₙ cos(ωₙt) + bₙ sin(ωₙt))] on each
+/// quadrature, n = 1..n_modes, with ωₙ = 2πn(1 + rₙ)/T and fixed random
+/// rₙ ∈ [-1/4, 1/4)
+fn crab(n_modes: usize, rng: &mut u64) -> Basis {
+    // Columns per quadrature as (frequency, sine?); the constant term has no sine partner
+    let mut columns = vec![(0.0, false)];
+    for m in 1..=n_modes {
+        let r = 0.5 * (next_uniform(rng) - 0.5);
+        let w = 2.0 * std::f64::consts::PI * m as f64 * (1.0 + r) / DURATION;
+        columns.push((w, false));
+        columns.push((w, true));
+    }
+    let per_channel = columns.len();
+    let n = 2 * per_channel;
+    let mut phi = vec![0.0; 2 * N_STEPS * n];
+    for channel in 0..2 {
+        for k in 0..N_STEPS {
+            let t = step_time(k);
+            let envelope = (std::f64::consts::PI * t / DURATION).sin();
+            let row = (channel * N_STEPS + k) * n;
+            for (c, &(w, is_sin)) in columns.iter().enumerate() {
+                let wave = if is_sin { (w * t).sin() } else { (w * t).cos() };
+                phi[row + channel * per_channel + c] = envelope * wave;
+            }
+        }
+    }
+    Basis {
+        name: "Fourier/CRAB",
+        n_params: n,
+        shape: Shape::Linear(phi),
+    }
+}
+
+/// Uniform cubic B-spline through n_points control points per quadrature
+///
+/// Three zero control points are added at each end, so the pulse starts and
+/// ends at zero and is twice continuously differentiable.
+fn bspline(n_points: usize) -> Basis {
+    let n = 2 * n_points;
+    let n_segments = n_points + 3;
+    let mut phi = vec![0.0; 2 * N_STEPS * n];
+    for k in 0..N_STEPS {
+        let s = step_time(k) / DURATION * n_segments as f64;
+        let seg = (s.floor() as usize).min(n_segments - 1);
+        let t = s - seg as f64;
+        // Cubic B-spline weights of padded control points seg..seg+3
+        let w = [
+            (1.0 - t).powi(3) / 6.0,
+            (3.0 * t.powi(3) - 6.0 * t * t + 4.0) / 6.0,
+            (-3.0 * t.powi(3) + 3.0 * t * t + 3.0 * t + 1.0) / 6.0,
+            t.powi(3) / 6.0,
+        ];
+        for (i, wi) in w.iter().enumerate() {
+            // Padded index p holds free control point p - 3
+            let p = seg + i;
+            if p < 3 || p >= n_points + 3 {
+                continue;
+            }
+            for channel in 0..2 {
+                let row = (channel * N_STEPS + k) * n;
+                phi[row + channel * n_points + p - 3] = *wi;
+            }
+        }
+    }
+    Basis {
+        name: "cubic B-spline",
+        n_params: n,
+        shape: Shape::Linear(phi),
+    }
+}
+
+fn gaussian() -> Basis {
+    Basis {
+        name: "Gaussian",
+        n_params: 2,
+        shape: Shape::Gaussian,
+    }
+}
+
+fn drag() -> Basis {
+    Basis {
+        name: "DRAG",
+        n_params: 3,
+        shape: Shape::Drag,
+    }
+}
+
+/// Deterministic pseudo-random numbers in [0, 1) (64-bit LCG)
+fn next_uniform(state: &mut u64) -> f64 {
+    *state = state
+        .wrapping_mul(6364136223846793005)
+        .wrapping_add(1442695040888963407);
+    (*state >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Starting point: amplitudes uniform in [-scale, scale) for the linear
+/// bases, a π-area Gaussian of width 0.15 for the analytic shapes
+fn initial_params(basis: &Basis, scale: f64, rng: &mut u64) -> Vec<f64> {
+    let sigma: f64 = 0.15;
+    let amp = std::f64::consts::PI / (sigma * (2.0 * std::f64::consts::PI).sqrt());
+    match basis.shape {
+        Shape::Linear(_) => (0..basis.n_params)
+            .map(|_| scale * (2.0 * next_uniform(rng) - 1.0))
+            .collect(),
+        Shape::Gaussian => vec![amp, sigma],
+        Shape::Drag => vec![amp, sigma, 0.0],
+    }
+}
+
+/// Adam on the shape parameters; returns the final gate fidelity
+fn optimize(params: &mut [f64], basis: &Basis, target: &Unitary, lr: f64) -> f64 {
+    let n = params.len();
+    let (beta1, beta2, epsilon) = (0.9, 0.999, 1e-8);
+    let mut m = vec![0.0; n];
+    let mut v = vec![0.0; n];
+    for iter in 0..N_ITERS {
+        let mut grad = vec![0.0; n];
+        d_gate_infidelity(params, &mut grad, basis, target, 1.0);
+        for i in 0..n {
+            m[i] = beta1 * m[i] + (1.0 - beta1) * grad[i];
+            v[i] = beta2 * v[i] + (1.0 - beta2) * grad[i] * grad[i];
+            let m_hat = m[i] / (1.0 - f64::powi(beta1, iter as i32 + 1));
+            let v_hat = v[i] / (1.0 - f64::powi(beta2, iter as i32 + 1));
+            params[i] -= lr * m_hat / (v_hat.sqrt() + epsilon);
+        }
+    }
+    1.0 - gate_infidelity(params, basis, target)
+}
+
+/// Largest jump between neighbouring steps and largest value at either end
+fn roughness(params: &[f64], basis: &Basis) -> (f64, f64) {
+    let mut ux = [0.0; N_STEPS];
+    let mut uy = [0.0; N_STEPS];
+    pulse(params, basis, &mut ux, &mut uy);
+    let mut jump: f64 = 0.0;
+    for u in [&ux, &uy] {
+        for k in 1..N_STEPS {
+            jump = jump.max((u[k] - u[k - 1]).abs());
+        }
+    }
+    let edge = [ux[0], uy[0], ux[N_STEPS - 1], uy[N_STEPS - 1]]
+        .iter()
+        .fold(0.0, |acc: f64, x| acc.max(x.abs()));
+    (jump, edge)
+}
+
+fn main() {
+    println!("Smooth Pulse Parametrizations");
+    println!("=============================\n");
+    println!("Target: X gate, T = {DURATION}, {N_STEPS} steps, drift ω₀ = {OMEGA0}\n");
+
+    let x_gate: Unitary = [0.0, 0.0, 1.0, 0.0, 1.0, 0.0, 0.0, 0.0];
+    let mut rng = 7;
+    let bases = [
+        // (basis, learning rate, initial amplitude scale)
+        (piecewise_constant(), 0.1, 2.0),
+        (crab(2, &mut rng), 0.05, 0.5),
+        (bspline(6), 0.1, 2.0),
+        (gaussian(), 0.01, 0.0),
+        (drag(), 0.01, 0.0),
+    ];
+
+    // 1. Gradient check: the chain rule runs through the pulse evaluation
+    println!("1. Gradient check (autodiff vs finite differences)");
+    let h = 1e-6;
+    let mut grad_err: f64 = 0.0;
+    for (basis, _, scale) in &bases {
+        let mut params = initial_params(basis, *scale, &mut rng);
+        if let Shape::Drag = basis.shape {
+            params[2] = 0.05;
+        }
+        let mut grad = vec![0.0; params.len()];
+        d_gate_infidelity(&params, &mut grad, basis, &x_gate, 1.0);
+        let mut err: f64 = 0.0;
+        for i in 0..params.len() {
+            let mut plus = params.clone();
+            let mut minus = params.clone();
+            plus[i] += h;
+            minus[i] -= h;
+            let fd = (gate_infidelity(&plus, basis, &x_gate)
+                - gate_infidelity(&minus, basis, &x_gate))
+                / (2.0 * h);
+            err = err.max((fd - grad[i]).abs());
+        }
+        println!("   {:<20} max |∇ - FD| = {err:.2e}", basis.name);
+        grad_err = grad_err.max(err);
+    }
+
+    // 2. Optimization in each basis
+    println!("\n2. X gate synthesis with Adam ({N_ITERS} iterations)");
+    println!(
+        "   {:<20} {:>6} {:>12} {:>12} {:>10}",
+        "basis", "params", "F", "max |Δu|", "max |edge|"
+    );
+    let mut results = Vec::new();
+    for (basis, lr, scale) in &bases {
+        let mut params = initial_params(basis, *scale, &mut rng);
+        let fidelity = optimize(&mut params, basis, &x_gate, *lr);
+        let (jump, edge) = roughness(&params, basis);
+        println!(
+            "   {:<20} {:>6} {fidelity:>12.8} {jump:>12.4} {edge:>10.4}",
+            basis.name, basis.n_params
+        );
+        if let Shape::Gaussian | Shape::Drag = basis.shape {
+            println!("      shape parameters: {params:.4?}");
+        }
+        results.push((basis.name, fidelity, jump));
+    }
+
+    println!("\n--- Verification ---");
+    if grad_err < 1e-6 {
+        println!("PASS: shape-parameter gradients match finite differences");
+    } else {
+        println!("FAIL: gradient error {grad_err:.2e}");
+    }
+    let piecewise_jump = results[0].2;
+    for (name, fidelity, jump) in &results[1..3] {
+        if *fidelity > 0.999 && *jump < piecewise_jump / 4.0 {
+            println!("SUCCESS: {name} reaches F = {fidelity:.6} with a smooth pulse");
+        } else {
+            println!("Unexpected: {name} F = {fidelity:.6}, max step {jump:.4}");
+        }
+    }
+    let (gaussian_f, drag_f) = (results[3].1, results[4].1);
+    if drag_f > gaussian_f {
+        println!("PASS: the DRAG quadrature improves on the plain Gaussian ({gaussian_f:.6} → {drag_f:.6})");
+    } else {
+        println!("Unexpected: DRAG F = {drag_f:.6} vs Gaussian F = {gaussian_f:.6}");
+    }
+}
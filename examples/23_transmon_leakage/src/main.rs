@@ -0,0 +1,308 @@
+//! Example 23: Leakage-Aware Transmon Control
+//!
+//! A transmon is a weakly anharmonic oscillator, so a drive at the qubit
+//! frequency also couples |1⟩ to |2⟩. Keeping three levels, in the frame
+//! rotating at the qubit frequency:
+//!
+//! H(t) = α|2⟩⟨2| + uₓ(t)(a + a†)/2 + u_y(t)·i(a† - a)/2
+//!
+//! with ⟨0|a|1⟩ = 1 and ⟨1|a|2⟩ = √2. A short pulse has a bandwidth comparable
+//! to the anharmonicity α and leaks population out of the qubit subspace.
+//!
+//! The objective is the subspace gate infidelity of an X gate plus a leakage
+//! penalty. Optimizing free I/Q amplitudes from a plain Gaussian, the
+//! gradient grows a quadrature u_y ∝ duₓ/dt on its own: the DRAG correction.
+//!
+//! Run with: RUSTFLAGS="-Z autodiff=Enable" cargo +enzyme run -p transmon_leakage
+
+#![feature(autodiff)]
+
+use std::autodiff::autodiff_reverse;
+
+const LEVELS: usize = 3;
+const N_STEPS: usize = 80;
+const DURATION: f64 = 4.0;
+const DT: f64 = DURATION / N_STEPS as f64;
+/// Anharmonicity: E₂ - 2E₁ in the rotating frame
+const ALPHA: f64 = -2.5;
+const SIGMA: f64 = DURATION / 4.0;
+const SQRT2: f64 = std::f64::consts::SQRT_2;
+/// Taylor order for exp(-iHτ) with ‖H‖τ ≤ 1/2
+const TAYLOR_ORDER: usize = 14;
+const LEAKAGE_WEIGHT: f64 = 1.0;
+const N_ITERS: usize = 500;
+
+/// Two propagated states, |0⟩ and |1⟩ evolved, each 3 complex amplitudes
+/// as (re, im) pairs: state s, level n at 2·(3s + n)
+type States = [f64; 4 * LEVELS];
+
+/// Hamiltonian for one step: 3×3 complex, row-major, (re, im) interleaved
+fn hamiltonian(ux: f64, uy: f64) -> [f64; 2 * LEVELS * LEVELS] {
+    let mut h = [0.0; 2 * LEVELS * LEVELS];
+    // H₂₂ = α
+    h[2 * 8] = ALPHA;
+    // H₀₁ = (uₓ - i u_y)/2, H₁₀ = conj
+    h[2] = ux / 2.0;
+    h[3] = -uy / 2.0;
+    h[2 * 3] = ux / 2.0;
+    h[2 * 3 + 1] = uy / 2.0;
+    // H₁₂ = √2(uₓ - i u_y)/2, H₂₁ = conj
+    h[2 * 5] = SQRT2 * ux / 2.0;
+    h[2 * 5 + 1] = -SQRT2 * uy / 2.0;
+    h[2 * 7] = SQRT2 * ux / 2.0;
+    h[2 * 7 + 1] = SQRT2 * uy / 2.0;
+    h
+}
+
+/// Both states ← exp(-iHΔt)·states, by a Taylor series on 2ˢ sub-steps
+fn apply_step(states: &mut States, ux: f64, uy: f64) {
+    let h = hamiltonian(ux, uy);
+
+    // Row-sum bound on ‖H‖
+    let mut norm = 0.0;
+    let mut i = 0;
+    while i < LEVELS {
+        let mut row = 0.0;
+        let mut j = 0;
+        while j < LEVELS {
+            let re = h[2 * (i * LEVELS + j)];
+            let im = h[2 * (i * LEVELS + j) + 1];
+            row += (if re < 0.0 { -re } else { re }) + (if im < 0.0 { -im } else { im });
+            j += 1;
+        }
+        if row > norm {
+            norm = row;
+        }
+        i += 1;
+    }
+    let mut n_sub = 1;
+    while norm * DT / n_sub as f64 > 0.5 {
+        n_sub *= 2;
+    }
+    let tau = DT / n_sub as f64;
+
+    let mut sub = 0;
+    while sub < n_sub {
+        let mut term = *states;
+        let mut acc = *states;
+        let mut n = 1;
+        while n <= TAYLOR_ORDER {
+            // term ← (-iτ/n) H term, for both states
+            let c = tau / n as f64;
+            let mut next = [0.0; 4 * LEVELS];
+            let mut s = 0;
+            while s < 2 {
+                let off = 2 * LEVELS * s;
+                let mut i = 0;
+                while i < LEVELS {
+                    let mut v_re = 0.0;
+                    let mut v_im = 0.0;
+                    let mut j = 0;
+                    while j < LEVELS {
+                        let h_re = h[2 * (i * LEVELS + j)];
+                        let h_im = h[2 * (i * LEVELS + j) + 1];
+                        v_re += h_re * term[off + 2 * j] - h_im * term[off + 2 * j + 1];
+                        v_im += h_re * term[off + 2 * j + 1] + h_im * term[off + 2 * j];
+                        j += 1;
+                    }
+                    next[off + 2 * i] = c * v_im;
+                    next[off + 2 * i + 1] = -c * v_re;
+                    i += 1;
+                }
+                s += 1;
+            }
+            let mut e = 0;
+            while e < 4 * LEVELS {
+                term[e] = next[e];
+                acc[e] += next[e];
+                e += 1;
+            }
+            n += 1;
+        }
+        *states = acc;
+        sub += 1;
+    }
+}
+
+/// Evolves |0⟩ and |1⟩; amplitudes are [uₓ₀ … uₓ₍ₙ₋₁₎, u_y₀ … u_y₍ₙ₋₁₎]
+fn evolve(amps: &[f64]) -> States {
+    let mut states = [0.0; 4 * LEVELS];
+    states[0] = 1.0; // |0⟩
+    states[2 * LEVELS + 2] = 1.0; // |1⟩
+    let mut k = 0;
+    while k < N_STEPS {
+        apply_step(&mut states, amps[k], amps[N_STEPS + k]);
+        k += 1;
+    }
+    states
+}
+
+/// Average population left in |2⟩: 1 - ½ Σ_{j∈{0,1}} Σ_{i∈{0,1}} |Uᵢⱼ|²
+fn leakage(states: &States) -> f64 {
+    let mut kept = 0.0;
+    let mut s = 0;
+    while s < 2 {
+        let mut i = 0;
+        while i < 2 {
+            let re = states[2 * (LEVELS * s + i)];
+            let im = states[2 * (LEVELS * s + i) + 1];
+            kept += re * re + im * im;
+            i += 1;
+        }
+        s += 1;
+    }
+    1.0 - kept / 2.0
+}
+
+/// Subspace X-gate fidelity |Tr(X† P U P)|² / 4 = |U₁₀ + U₀₁|² / 4
+fn subspace_fidelity(states: &States) -> f64 {
+    // U₁₀ = ⟨1|U|0⟩ (state 0, level 1), U₀₁ = ⟨0|U|1⟩ (state 1, level 0)
+    let re = states[2] + states[2 * LEVELS];
+    let im = states[3] + states[2 * LEVELS + 1];
+    (re * re + im * im) / 4.0
+}
+
+/// Subspace infidelity plus the leakage penalty
+#[autodiff_reverse(d_objective, Duplicated, Active)]
+fn objective(amps: &[f64]) -> f64 {
+    let states = evolve(amps);
+    1.0 - subspace_fidelity(&states) + LEAKAGE_WEIGHT * leakage(&states)
+}
+
+// ---------------------------------------------------------------------------
+// Pulses and analysis (plain Rust, not differentiated)
+// ---------------------------------------------------------------------------
+
+fn step_time(k: usize) -> f64 {
+    (k as f64 + 0.5) * DT
+}
+
+/// π-area Gaussian on x plus the quadrature u_y = β·duₓ/dt
+fn drag_pulse(beta: f64) -> Vec<f64> {
+    let amp = std::f64::consts::PI / (SIGMA * (2.0 * std::f64::consts::PI).sqrt());
+    let mut amps = vec![0.0; 2 * N_STEPS];
+    for k in 0..N_STEPS {
+        let s = step_time(k) - DURATION / 2.0;
+        let g = amp * (-s * s / (2.0 * SIGMA * SIGMA)).exp();
+        amps[k] = g;
+        amps[N_STEPS + k] = -beta * s / (SIGMA * SIGMA) * g;
+    }
+    amps
+}
+
+fn report(name: &str, amps: &[f64]) -> (f64, f64) {
+    let states = evolve(amps);
+    // Clamp the rounding noise of 1 - Σ|Uᵢⱼ|²/2 around zero
+    let (fidelity, leak) = (subspace_fidelity(&states), leakage(&states).max(0.0));
+    println!("   {name:<24} F = {fidelity:.8}   leakage = {leak:.2e}");
+    (fidelity, leak)
+}
+
+/// Least-squares fit u_y ≈ β·duₓ/dt on the grid, and the correlation of the two
+fn fit_drag(amps: &[f64]) -> (f64, f64) {
+    let (ux, uy) = amps.split_at(N_STEPS);
+    // Central differences for duₓ/dt, one-sided at the ends
+    let dux: Vec<f64> = (0..N_STEPS)
+        .map(|k| {
+            let (lo, hi) = (k.saturating_sub(1), (k + 1).min(N_STEPS - 1));
+            (ux[hi] - ux[lo]) / ((hi - lo) as f64 * DT)
+        })
+        .collect();
+    let dot = |a: &[f64], b: &[f64]| a.iter().zip(b).map(|(x, y)| x * y).sum::<f64>();
+    let beta = dot(uy, &dux) / dot(&dux, &dux);
+    let corr = dot(uy, &dux) / (dot(uy, uy) * dot(&dux, &dux)).sqrt();
+    (beta, corr)
+}
+
+fn main() {
+    println!("Leakage-Aware Transmon Control");
+    println!("==============================\n");
+    println!("Three levels, α = {ALPHA}, X gate in T = {DURATION} ({N_STEPS} steps)");
+    println!("Gaussian envelope σ = {SIGMA}\n");
+
+    // 1. Plain Gaussian vs a scan over the DRAG coefficient
+    println!("1. Analytic pulses");
+    let (gauss_f, gauss_leak) = report("Gaussian", &drag_pulse(0.0));
+    let mut best = (0.0, f64::INFINITY);
+    for i in -40..=40 {
+        let beta = i as f64 * 0.05;
+        let states = evolve(&drag_pulse(beta));
+        let leak = leakage(&states);
+        if leak < best.1 {
+            best = (beta, leak);
+        }
+    }
+    let best_beta = best.0;
+    println!(
+        "   lowest-leakage β from a scan: {best_beta:.2}  (first-order DRAG: -1/(2α) = {:.2})",
+        -1.0 / (2.0 * ALPHA)
+    );
+    report(&format!("DRAG, β = {best_beta:.2}"), &drag_pulse(best_beta));
+
+    // 2. Gradient check
+    println!("\n2. Gradient check (autodiff vs finite differences)");
+    let amps = drag_pulse(0.3);
+    let mut grad = vec![0.0; amps.len()];
+    d_objective(&amps, &mut grad, 1.0);
+    let h = 1e-6;
+    let mut grad_err: f64 = 0.0;
+    for i in (0..amps.len()).step_by(3) {
+        let mut plus = amps.clone();
+        let mut minus = amps.clone();
+        plus[i] += h;
+        minus[i] -= h;
+        let fd = (objective(&plus) - objective(&minus)) / (2.0 * h);
+        grad_err = grad_err.max((fd - grad[i]).abs());
+    }
+    println!("   max |∇ - FD| (every 3rd amplitude) = {grad_err:.2e}");
+
+    // 3. Free I/Q optimization starting from the Gaussian, u_y = 0
+    println!(
+        "\n3. Optimizing all {} I/Q amplitudes from the Gaussian ({N_ITERS} Adam iterations)",
+        2 * N_STEPS
+    );
+    let mut amps = drag_pulse(0.0);
+    let n = amps.len();
+    let (beta1, beta2, epsilon, lr) = (0.9, 0.999, 1e-8, 0.002);
+    let mut m = vec![0.0; n];
+    let mut v = vec![0.0; n];
+    for iter in 0..N_ITERS {
+        let mut grad = vec![0.0; n];
+        let loss = d_objective(&amps, &mut grad, 1.0);
+        for i in 0..n {
+            m[i] = beta1 * m[i] + (1.0 - beta1) * grad[i];
+            v[i] = beta2 * v[i] + (1.0 - beta2) * grad[i] * grad[i];
+            let m_hat = m[i] / (1.0 - f64::powi(beta1, iter as i32 + 1));
+            let v_hat = v[i] / (1.0 - f64::powi(beta2, iter as i32 + 1));
+            amps[i] -= lr * m_hat / (v_hat.sqrt() + epsilon);
+        }
+        if iter % 100 == 0 {
+            println!("   iter {iter:4}: objective = {loss:.3e}");
+        }
+    }
+    let (opt_f, opt_leak) = report("optimized I/Q", &amps);
+    let (fit_beta, corr) = fit_drag(&amps);
+    println!("   fit u_y ≈ β·duₓ/dt: β = {fit_beta:.3}, correlation = {corr:.3}");
+
+    println!("\n--- Verification ---");
+    if grad_err < 1e-6 {
+        println!("PASS: gradients match finite differences");
+    } else {
+        println!("FAIL: gradient error {grad_err:.2e}");
+    }
+    if opt_leak < gauss_leak / 100.0 && opt_f > gauss_f {
+        println!(
+            "PASS: leakage {gauss_leak:.2e} → {opt_leak:.2e}, fidelity {gauss_f:.6} → {opt_f:.6}"
+        );
+    } else {
+        println!("FAIL: optimized leakage {opt_leak:.2e}, fidelity {opt_f:.6}");
+    }
+    let drag_beta = -1.0 / (2.0 * ALPHA);
+    if corr > 0.8 && (fit_beta - drag_beta).abs() < 0.2 * drag_beta {
+        println!(
+            "SUCCESS: the optimized quadrature is DRAG-like (β = {fit_beta:.3} vs -1/(2α) = {drag_beta:.3})"
+        );
+    } else {
+        println!("Unexpected: fitted β = {fit_beta:.3}, correlation with duₓ/dt {corr:.3}");
+    }
+}
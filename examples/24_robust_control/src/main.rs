@@ -0,0 +1,325 @@
+//! Example 24: Robust Pulses over Detuning and Amplitude Errors
+//!
+//! Examples 14 and 19 tune the pulse for one exact drift frequency ω₀ and a
+//! perfectly calibrated amplitude. A real qubit has a detuning δ and an
+//! amplitude scale error ε, both known only up to a distribution:
+//!
+//! H(t) = (ω₀ + δ)σz/2 + (1 + ε)[uₓ(t)σx/2 + u_y(t)σy/2]
+//!
+//! The robust objective is the expected gate infidelity over δ and ε,
+//! approximated with Gauss-Hermite quadrature:
+//!
+//! E[1 - F] ≈ Σᵢ wᵢ (1 - F(u; δᵢ, εᵢ))
+//!
+//! It is still one scalar function of the controls, so one reverse pass gives
+//! the gradient of the whole ensemble average.
+//!
+//! Run with: RUSTFLAGS="-Z autodiff=Enable" cargo +enzyme run -p robust_control
+
+#![feature(autodiff)]
+
+use std::autodiff::autodiff_reverse;
+
+const N_STEPS: usize = 100;
+const DURATION: f64 = 3.0;
+const DT: f64 = DURATION / N_STEPS as f64;
+const N_ITERS: usize = 2000;
+const OMEGA0: f64 = 1.0; // Nominal drift frequency
+const DIM: f64 = 2.0;
+/// Standard deviations of the detuning and the relative amplitude error
+const SIGMA_DETUNING: f64 = 0.1;
+const SIGMA_SCALE: f64 = 0.05;
+
+/// Taylor series sin(x) - high precision
+fn my_sin(x: f64) -> f64 {
+    let mut sum = x;
+    let mut term = x;
+    let mut k = 1;
+    while k < 30 {
+        term *= -x * x / ((2 * k) as f64 * (2 * k + 1) as f64);
+        sum += term;
+        k += 1;
+    }
+    sum
+}
+
+/// Taylor series cos(x) - high precision
+fn my_cos(x: f64) -> f64 {
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    let mut k = 1;
+    while k < 30 {
+        term *= -x * x / ((2 * k - 1) as f64 * (2 * k) as f64);
+        sum += term;
+        k += 1;
+    }
+    sum
+}
+
+/// Newton-Raphson sqrt with range reduction into [0.25, 1]
+fn my_sqrt(x: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    let mut m = x;
+    let mut scale = 1.0;
+    while m > 1.0 {
+        m /= 4.0;
+        scale *= 2.0;
+    }
+    while m < 0.25 {
+        m *= 4.0;
+        scale /= 2.0;
+    }
+    let mut guess = 1.0;
+    let mut i = 0;
+    while i < 8 {
+        guess = (guess + m / guess) / 2.0;
+        i += 1;
+    }
+    guess * scale
+}
+
+/// 2×2 complex matrix, row-major, (re, im) interleaved
+type Unitary = [f64; 8];
+
+const IDENTITY: Unitary = [1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0];
+
+/// Exact step U ← exp(-i(n·σ)θ/2) U with θ = |ω|Δt, ω = (uₓ, u_y, ω_z)
+fn apply_exact_step(u: &mut Unitary, ux: f64, uy: f64, omega_z: f64) {
+    let omega_eff = my_sqrt(omega_z * omega_z + ux * ux + uy * uy);
+    let theta = omega_eff * DT;
+    let nx = ux / omega_eff;
+    let ny = uy / omega_eff;
+    let nz = omega_z / omega_eff;
+
+    let c = my_cos(theta / 2.0);
+    let s = my_sin(theta / 2.0);
+
+    let (s00_re, s00_im) = (c, -s * nz);
+    let (s01_re, s01_im) = (-s * ny, -s * nx);
+    let (s10_re, s10_im) = (s * ny, -s * nx);
+    let (s11_re, s11_im) = (c, s * nz);
+
+    let mut col = 0;
+    while col < 2 {
+        let (a_re, a_im) = (u[2 * col], u[2 * col + 1]);
+        let (b_re, b_im) = (u[4 + 2 * col], u[4 + 2 * col + 1]);
+        u[2 * col] = s00_re * a_re - s00_im * a_im + s01_re * b_re - s01_im * b_im;
+        u[2 * col + 1] = s00_re * a_im + s00_im * a_re + s01_re * b_im + s01_im * b_re;
+        u[4 + 2 * col] = s10_re * a_re - s10_im * a_im + s11_re * b_re - s11_im * b_im;
+        u[4 + 2 * col + 1] = s10_re * a_im + s10_im * a_re + s11_re * b_im + s11_im * b_re;
+        col += 1;
+    }
+}
+
+/// Gate infidelity 1 - |Tr(U_target† U)|²/d² for one detuning and amplitude scale
+fn member_infidelity(controls: &[f64], target: &[f64], detuning: f64, scale: f64) -> f64 {
+    let mut u = IDENTITY;
+    let mut k = 0;
+    while k < N_STEPS {
+        apply_exact_step(
+            &mut u,
+            scale * controls[2 * k],
+            scale * controls[2 * k + 1],
+            OMEGA0 + detuning,
+        );
+        k += 1;
+    }
+    let mut tr_re = 0.0;
+    let mut tr_im = 0.0;
+    let mut e = 0;
+    while e < 4 {
+        tr_re += target[2 * e] * u[2 * e] + target[2 * e + 1] * u[2 * e + 1];
+        tr_im += target[2 * e] * u[2 * e + 1] - target[2 * e + 1] * u[2 * e];
+        e += 1;
+    }
+    1.0 - (tr_re * tr_re + tr_im * tr_im) / (DIM * DIM)
+}
+
+/// Weighted samples (δᵢ, 1 + εᵢ, wᵢ) of the uncertain parameters
+struct Ensemble {
+    detunings: Vec<f64>,
+    scales: Vec<f64>,
+    weights: Vec<f64>,
+}
+
+/// Expected infidelity over the ensemble
+#[autodiff_reverse(d_robust_infidelity, Duplicated, Const, Const, Active)]
+fn robust_infidelity(controls: &[f64], target: &[f64], ensemble: &Ensemble) -> f64 {
+    let mut total = 0.0;
+    let mut i = 0;
+    while i < ensemble.weights.len() {
+        total += ensemble.weights[i]
+            * member_infidelity(controls, target, ensemble.detunings[i], ensemble.scales[i]);
+        i += 1;
+    }
+    total
+}
+
+// ---------------------------------------------------------------------------
+// Ensembles and optimization (plain Rust, not differentiated)
+// ---------------------------------------------------------------------------
+
+/// 5-point Gauss-Hermite rule for a standard normal variable: (node, weight)
+const GAUSS_HERMITE_5: [(f64, f64); 5] = [
+    (-2.856970013872806, 0.011257411327721),
+    (-1.355626179974266, 0.222075922005613),
+    (0.0, 0.533333333333333),
+    (1.355626179974266, 0.222075922005613),
+    (2.856970013872806, 0.011257411327721),
+];
+
+/// The single nominal point δ = 0, ε = 0
+fn nominal() -> Ensemble {
+    Ensemble {
+        detunings: vec![0.0],
+        scales: vec![1.0],
+        weights: vec![1.0],
+    }
+}
+
+/// Tensor-product Gauss-Hermite rule for independent normal δ and ε (25 samples)
+fn gauss_hermite(sigma_detuning: f64, sigma_scale: f64) -> Ensemble {
+    let mut ensemble = Ensemble {
+        detunings: Vec::new(),
+        scales: Vec::new(),
+        weights: Vec::new(),
+    };
+    for (x_d, w_d) in GAUSS_HERMITE_5 {
+        for (x_s, w_s) in GAUSS_HERMITE_5 {
+            ensemble.detunings.push(sigma_detuning * x_d);
+            ensemble.scales.push(1.0 + sigma_scale * x_s);
+            ensemble.weights.push(w_d * w_s);
+        }
+    }
+    ensemble
+}
+
+/// Deterministic pseudo-random numbers in [0, 1) (64-bit LCG)
+fn next_uniform(state: &mut u64) -> f64 {
+    *state = state
+        .wrapping_mul(6364136223846793005)
+        .wrapping_add(1442695040888963407);
+    (*state >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Adam on the controls for the given ensemble; returns the final objective
+fn optimize(controls: &mut [f64], target: &Unitary, ensemble: &Ensemble) -> f64 {
+    let n = controls.len();
+    let (beta1, beta2, epsilon, lr) = (0.9, 0.999, 1e-8, 0.05);
+    let mut m = vec![0.0; n];
+    let mut v = vec![0.0; n];
+    for iter in 0..N_ITERS {
+        let mut grad = vec![0.0; n];
+        d_robust_infidelity(controls, &mut grad, target, ensemble, 1.0);
+        for i in 0..n {
+            m[i] = beta1 * m[i] + (1.0 - beta1) * grad[i];
+            v[i] = beta2 * v[i] + (1.0 - beta2) * grad[i] * grad[i];
+            let m_hat = m[i] / (1.0 - f64::powi(beta1, iter as i32 + 1));
+            let v_hat = v[i] / (1.0 - f64::powi(beta2, iter as i32 + 1));
+            controls[i] -= lr * m_hat / (v_hat.sqrt() + epsilon);
+        }
+    }
+    robust_infidelity(controls, target, ensemble)
+}
+
+fn main() {
+    println!("Robust Pulse Optimization");
+    println!("=========================\n");
+    println!("X gate, T = {DURATION}, {N_STEPS} steps, x/y controls");
+    println!("Uncertainty: δ ~ N(0, {SIGMA_DETUNING}²), ε ~ N(0, {SIGMA_SCALE}²)\n");
+
+    let x_gate: Unitary = [0.0, 0.0, 1.0, 0.0, 1.0, 0.0, 0.0, 0.0];
+    let ensemble = gauss_hermite(SIGMA_DETUNING, SIGMA_SCALE);
+    let weight_sum: f64 = ensemble.weights.iter().sum();
+    println!(
+        "Gauss-Hermite ensemble: {} samples, Σw = {weight_sum:.12}\n",
+        ensemble.weights.len()
+    );
+
+    let mut rng = 17;
+    let initial: Vec<f64> = (0..2 * N_STEPS)
+        .map(|_| 2.0 * next_uniform(&mut rng) - 1.0)
+        .collect();
+
+    // 1. Gradient check on the ensemble objective
+    println!("1. Gradient check (autodiff vs finite differences)");
+    let mut grad = vec![0.0; initial.len()];
+    d_robust_infidelity(&initial, &mut grad, &x_gate, &ensemble, 1.0);
+    let h = 1e-6;
+    let mut grad_err: f64 = 0.0;
+    for i in (0..initial.len()).step_by(7) {
+        let mut plus = initial.clone();
+        let mut minus = initial.clone();
+        plus[i] += h;
+        minus[i] -= h;
+        let fd = (robust_infidelity(&plus, &x_gate, &ensemble)
+            - robust_infidelity(&minus, &x_gate, &ensemble))
+            / (2.0 * h);
+        grad_err = grad_err.max((fd - grad[i]).abs());
+    }
+    println!("   max |∇ - FD| (every 7th control) = {grad_err:.2e}\n");
+
+    // 2. Nominal and robust pulses from the same starting point
+    println!("2. Optimizing ({N_ITERS} Adam iterations each)");
+    let mut nominal_pulse = initial.clone();
+    optimize(&mut nominal_pulse, &x_gate, &nominal());
+    let mut robust_pulse = initial;
+    optimize(&mut robust_pulse, &x_gate, &ensemble);
+    let expected = |pulse: &[f64]| 1.0 - robust_infidelity(pulse, &x_gate, &ensemble);
+    let at = |pulse: &[f64], d: f64, s: f64| 1.0 - member_infidelity(pulse, &x_gate, d, s);
+    println!("   {:<10} {:>14} {:>14}", "pulse", "F(δ=0, ε=0)", "E[F]");
+    for (name, pulse) in [("nominal", &nominal_pulse), ("robust", &robust_pulse)] {
+        println!(
+            "   {name:<10} {:>14.8} {:>14.8}",
+            at(pulse, 0.0, 1.0),
+            expected(pulse)
+        );
+    }
+
+    // 3. Fidelity vs detuning and vs amplitude error
+    println!("\n3. Fidelity vs detuning (ε = 0)");
+    println!("   {:>8} {:>12} {:>12}", "δ", "nominal", "robust");
+    let mut worst = [1.0_f64; 2];
+    for i in -6..=6 {
+        let d = i as f64 * 0.05;
+        let f = [at(&nominal_pulse, d, 1.0), at(&robust_pulse, d, 1.0)];
+        if d.abs() <= 2.0 * SIGMA_DETUNING + 1e-12 {
+            worst[0] = worst[0].min(f[0]);
+            worst[1] = worst[1].min(f[1]);
+        }
+        println!("   {d:>8.2} {:>12.6} {:>12.6}", f[0], f[1]);
+    }
+    println!("\n   Fidelity vs amplitude error (δ = 0)");
+    println!("   {:>8} {:>12} {:>12}", "ε", "nominal", "robust");
+    for i in -4..=4 {
+        let e = i as f64 * 0.05;
+        println!(
+            "   {e:>8.2} {:>12.6} {:>12.6}",
+            at(&nominal_pulse, 0.0, 1.0 + e),
+            at(&robust_pulse, 0.0, 1.0 + e)
+        );
+    }
+
+    println!("\n--- Verification ---");
+    if (weight_sum - 1.0).abs() < 1e-9 {
+        println!("PASS: quadrature weights sum to 1");
+    } else {
+        println!("FAIL: quadrature weights sum to {weight_sum}");
+    }
+    if grad_err < 1e-6 {
+        println!("PASS: ensemble gradients match finite differences");
+    } else {
+        println!("FAIL: gradient error {grad_err:.2e}");
+    }
+    let (e_nominal, e_robust) = (expected(&nominal_pulse), expected(&robust_pulse));
+    if e_robust > e_nominal && worst[1] > worst[0] {
+        println!(
+            "SUCCESS: robust pulse E[F] = {e_robust:.6} vs {e_nominal:.6}, worst F for |δ| ≤ 2σ = {:.6} vs {:.6}",
+            worst[1], worst[0]
+        );
+    } else {
+        println!("Unexpected: robust E[F] = {e_robust:.6}, nominal E[F] = {e_nominal:.6}");
+    }
+}
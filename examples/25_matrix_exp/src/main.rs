@@ -0,0 +1,579 @@
+//! Example 25: Differentiable Matrix Exponential
+//!
+//! Example 14's exact step uses the closed form of exp(-i(n·σ)θ/2), which
+//! exists only for 2×2 matrices. This example computes exp(A) for a complex
+//! n×n matrix, n ≤ MAX_DIM = 4, with scaling and squaring:
+//!
+//! 1. Scale A by 2⁻ˢ until its norm is at most 1/2
+//! 2. Approximate exp(A/2ˢ), by a [6/6] Padé approximant or a Taylor series
+//! 3. Square the result s times
+//!
+//! Everything is written in Enzyme-safe `while` loops over fixed-size buffers
+//! (raise `MAX_DIM` for larger matrices), so exp(A) can be differentiated
+//! w.r.t. the entries of A in reverse mode and w.r.t. a scalar time t in
+//! forward mode.
+//!
+//! Run with: RUSTFLAGS="-Z autodiff=Enable" cargo +enzyme run -p matrix_exp
+
+#![feature(autodiff)]
+
+use std::autodiff::{autodiff_forward, autodiff_reverse};
+
+/// Largest supported dimension (sizes the stack buffers)
+const MAX_DIM: usize = 4;
+const MAX_BUF: usize = 2 * MAX_DIM * MAX_DIM;
+/// Padé degree q of the [q/q] approximant
+const PADE_DEGREE: usize = 6;
+/// Taylor order for ‖B‖ ≤ 1/2: truncation error < 1e-20
+const TAYLOR_ORDER: usize = 16;
+
+/// Complex n×n matrix, row-major, (re, im) interleaved: element (i, j) at 2·(i·n + j)
+type Mat = [f64; MAX_BUF];
+
+/// C = A B
+fn mat_mul(a: &Mat, b: &Mat, n: usize) -> Mat {
+    let mut c = [0.0; MAX_BUF];
+    let mut i = 0;
+    while i < n {
+        let mut j = 0;
+        while j < n {
+            let mut re = 0.0;
+            let mut im = 0.0;
+            let mut k = 0;
+            while k < n {
+                let (a_re, a_im) = (a[2 * (i * n + k)], a[2 * (i * n + k) + 1]);
+                let (b_re, b_im) = (b[2 * (k * n + j)], b[2 * (k * n + j) + 1]);
+                re += a_re * b_re - a_im * b_im;
+                im += a_re * b_im + a_im * b_re;
+                k += 1;
+            }
+            c[2 * (i * n + j)] = re;
+            c[2 * (i * n + j) + 1] = im;
+            j += 1;
+        }
+        i += 1;
+    }
+    c
+}
+
+fn identity(n: usize) -> Mat {
+    let mut m = [0.0; MAX_BUF];
+    let mut i = 0;
+    while i < n {
+        m[2 * (i * n + i)] = 1.0;
+        i += 1;
+    }
+    m
+}
+
+/// Max row sum of |re| + |im|, an upper bound on the operator norm
+fn norm_bound(a: &Mat, n: usize) -> f64 {
+    let mut norm = 0.0;
+    let mut i = 0;
+    while i < n {
+        let mut row = 0.0;
+        let mut j = 0;
+        while j < n {
+            let re = a[2 * (i * n + j)];
+            let im = a[2 * (i * n + j) + 1];
+            row += (if re < 0.0 { -re } else { re }) + (if im < 0.0 { -im } else { im });
+            j += 1;
+        }
+        if row > norm {
+            norm = row;
+        }
+        i += 1;
+    }
+    norm
+}
+
+/// Step 1: B = A/2ˢ with ‖B‖ ≤ 1/2; returns (B, s)
+fn scale_down(a: &Mat, n: usize) -> (Mat, usize) {
+    let norm = norm_bound(a, n);
+    let mut scale = 1.0;
+    let mut squarings = 0;
+    while norm * scale > 0.5 {
+        scale /= 2.0;
+        squarings += 1;
+    }
+    let mut b = [0.0; MAX_BUF];
+    let mut e = 0;
+    while e < 2 * n * n {
+        b[e] = a[e] * scale;
+        e += 1;
+    }
+    (b, squarings)
+}
+
+/// Step 3: X ← X^(2ˢ)
+fn square_up(x: &Mat, squarings: usize, n: usize) -> Mat {
+    let mut r = *x;
+    let mut s = 0;
+    while s < squarings {
+        r = mat_mul(&r, &r, n);
+        s += 1;
+    }
+    r
+}
+
+/// exp(A) with a Taylor series for the scaled matrix
+fn expm_taylor(a: &Mat, n: usize) -> Mat {
+    assert!(n <= MAX_DIM, "n = {n} exceeds MAX_DIM = {MAX_DIM}");
+    let (b, squarings) = scale_down(a, n);
+    let mut term = identity(n);
+    let mut acc = identity(n);
+    let mut k = 1;
+    while k <= TAYLOR_ORDER {
+        let next = mat_mul(&term, &b, n);
+        let mut e = 0;
+        while e < 2 * n * n {
+            term[e] = next[e] / k as f64;
+            acc[e] += term[e];
+            e += 1;
+        }
+        k += 1;
+    }
+    square_up(&acc, squarings, n)
+}
+
+/// Solves Q X = P for X by Gaussian elimination with partial pivoting
+fn solve(q: &Mat, p: &Mat, n: usize) -> Mat {
+    let mut a = *q;
+    let mut x = *p;
+    let mut col = 0;
+    while col < n {
+        // Pivot: the row with the largest |a(r, col)|²
+        let mut pivot = col;
+        let mut best = -1.0;
+        let mut r = col;
+        while r < n {
+            let (re, im) = (a[2 * (r * n + col)], a[2 * (r * n + col) + 1]);
+            if re * re + im * im > best {
+                best = re * re + im * im;
+                pivot = r;
+            }
+            r += 1;
+        }
+        if pivot != col {
+            let mut e = 0;
+            while e < 2 * n {
+                let (i1, i2) = (2 * col * n + e, 2 * pivot * n + e);
+                a.swap(i1, i2);
+                x.swap(i1, i2);
+                e += 1;
+            }
+        }
+
+        // Eliminate below the pivot: row_r ← row_r - (a(r, col)/a(col, col))·row_col
+        let (p_re, p_im) = (a[2 * (col * n + col)], a[2 * (col * n + col) + 1]);
+        let p_abs2 = p_re * p_re + p_im * p_im;
+        let mut r = col + 1;
+        while r < n {
+            let (v_re, v_im) = (a[2 * (r * n + col)], a[2 * (r * n + col) + 1]);
+            // f = v / p = v·conj(p) / |p|²
+            let f_re = (v_re * p_re + v_im * p_im) / p_abs2;
+            let f_im = (v_im * p_re - v_re * p_im) / p_abs2;
+            let mut j = 0;
+            while j < n {
+                let (c_re, c_im) = (a[2 * (col * n + j)], a[2 * (col * n + j) + 1]);
+                a[2 * (r * n + j)] -= f_re * c_re - f_im * c_im;
+                a[2 * (r * n + j) + 1] -= f_re * c_im + f_im * c_re;
+                let (c_re, c_im) = (x[2 * (col * n + j)], x[2 * (col * n + j) + 1]);
+                x[2 * (r * n + j)] -= f_re * c_re - f_im * c_im;
+                x[2 * (r * n + j) + 1] -= f_re * c_im + f_im * c_re;
+                j += 1;
+            }
+            r += 1;
+        }
+        col += 1;
+    }
+
+    // Back substitution, one right-hand-side column at a time
+    let mut j = 0;
+    while j < n {
+        let mut i = n;
+        while i > 0 {
+            i -= 1;
+            let mut s_re = x[2 * (i * n + j)];
+            let mut s_im = x[2 * (i * n + j) + 1];
+            let mut k = i + 1;
+            while k < n {
+                let (a_re, a_im) = (a[2 * (i * n + k)], a[2 * (i * n + k) + 1]);
+                let (y_re, y_im) = (x[2 * (k * n + j)], x[2 * (k * n + j) + 1]);
+                s_re -= a_re * y_re - a_im * y_im;
+                s_im -= a_re * y_im + a_im * y_re;
+                k += 1;
+            }
+            let (d_re, d_im) = (a[2 * (i * n + i)], a[2 * (i * n + i) + 1]);
+            let d_abs2 = d_re * d_re + d_im * d_im;
+            x[2 * (i * n + j)] = (s_re * d_re + s_im * d_im) / d_abs2;
+            x[2 * (i * n + j) + 1] = (s_im * d_re - s_re * d_im) / d_abs2;
+        }
+        j += 1;
+    }
+    x
+}
+
+/// exp(A) with the [q/q] Padé approximant Q(B)⁻¹P(B) for the scaled matrix
+///
+/// P(B) = Σₖ cₖ Bᵏ, Q(B) = Σₖ (-1)ᵏ cₖ Bᵏ, cₖ = (2q-k)! q! / ((2q)! k! (q-k)!)
+fn expm_pade(a: &Mat, n: usize) -> Mat {
+    assert!(n <= MAX_DIM, "n = {n} exceeds MAX_DIM = {MAX_DIM}");
+    let (b, squarings) = scale_down(a, n);
+    let mut p = identity(n);
+    let mut q = identity(n);
+    let mut power = identity(n);
+    let mut c = 1.0;
+    let mut k = 1;
+    while k <= PADE_DEGREE {
+        // cₖ = cₖ₋₁ · (q - k + 1) / (k (2q - k + 1))
+        c *= (PADE_DEGREE - k + 1) as f64 / (k * (2 * PADE_DEGREE - k + 1)) as f64;
+        power = mat_mul(&power, &b, n);
+        let sign = if k % 2 == 0 { 1.0 } else { -1.0 };
+        let mut e = 0;
+        while e < 2 * n * n {
+            p[e] += c * power[e];
+            q[e] += sign * c * power[e];
+            e += 1;
+        }
+        k += 1;
+    }
+    let r = solve(&q, &p, n);
+    square_up(&r, squarings, n)
+}
+
+/// Copies a slice into a fixed-size buffer
+fn to_mat(a: &[f64], n: usize) -> Mat {
+    assert!(n <= MAX_DIM, "n = {n} exceeds MAX_DIM = {MAX_DIM}");
+    assert!(
+        a.len() >= 2 * n * n,
+        "expected {} entries for n = {n}",
+        2 * n * n
+    );
+    let mut m = [0.0; MAX_BUF];
+    let mut e = 0;
+    while e < 2 * n * n {
+        m[e] = a[e];
+        e += 1;
+    }
+    m
+}
+
+/// Σ wₑ·exp(A)ₑ over the interleaved entries: its gradient w.r.t. A is the
+/// vector-Jacobian product of exp with w
+#[autodiff_reverse(d_pade_probe, Duplicated, Const, Const, Active)]
+fn pade_probe(a: &[f64], w: &[f64], n: usize) -> f64 {
+    let e = expm_pade(&to_mat(a, n), n);
+    let mut sum = 0.0;
+    let mut i = 0;
+    while i < 2 * n * n {
+        sum += w[i] * e[i];
+        i += 1;
+    }
+    sum
+}
+
+#[autodiff_reverse(d_taylor_probe, Duplicated, Const, Const, Active)]
+fn taylor_probe(a: &[f64], w: &[f64], n: usize) -> f64 {
+    let e = expm_taylor(&to_mat(a, n), n);
+    let mut sum = 0.0;
+    let mut i = 0;
+    while i < 2 * n * n {
+        sum += w[i] * e[i];
+        i += 1;
+    }
+    sum
+}
+
+/// Σ wₑ·exp(tA)ₑ as a function of t, for forward mode
+#[autodiff_forward(d_pade_time, Dual, Const, Const, Const, Dual)]
+fn pade_time(t: f64, a: &[f64], w: &[f64], n: usize) -> f64 {
+    let mut ta = to_mat(a, n);
+    let mut e = 0;
+    while e < 2 * n * n {
+        ta[e] *= t;
+        e += 1;
+    }
+    let x = expm_pade(&ta, n);
+    let mut sum = 0.0;
+    let mut i = 0;
+    while i < 2 * n * n {
+        sum += w[i] * x[i];
+        i += 1;
+    }
+    sum
+}
+
+// ---------------------------------------------------------------------------
+// Reference results (plain Rust, not differentiated)
+// ---------------------------------------------------------------------------
+
+/// Eigen-decomposition of a real symmetric matrix by cyclic Jacobi rotations;
+/// returns (eigenvalues, eigenvectors as columns, row-major)
+fn jacobi_eigen(mut a: Vec<f64>, m: usize) -> (Vec<f64>, Vec<f64>) {
+    let mut v = vec![0.0; m * m];
+    for i in 0..m {
+        v[i * m + i] = 1.0;
+    }
+    for _sweep in 0..50 {
+        let off: f64 = (0..m)
+            .flat_map(|i| (0..m).filter(move |&j| j != i).map(move |j| (i, j)))
+            .map(|(i, j)| a[i * m + j] * a[i * m + j])
+            .sum();
+        if off < 1e-30 {
+            break;
+        }
+        for p in 0..m {
+            for q in p + 1..m {
+                if a[p * m + q].abs() < 1e-300 {
+                    continue;
+                }
+                let theta = (a[q * m + q] - a[p * m + p]) / (2.0 * a[p * m + q]);
+                let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+                let t = if theta == 0.0 { 1.0 } else { t };
+                let c = 1.0 / (t * t + 1.0).sqrt();
+                let s = t * c;
+                for k in 0..m {
+                    let (akp, akq) = (a[k * m + p], a[k * m + q]);
+                    a[k * m + p] = c * akp - s * akq;
+                    a[k * m + q] = s * akp + c * akq;
+                }
+                for k in 0..m {
+                    let (apk, aqk) = (a[p * m + k], a[q * m + k]);
+                    a[p * m + k] = c * apk - s * aqk;
+                    a[q * m + k] = s * apk + c * aqk;
+                }
+                for k in 0..m {
+                    let (vkp, vkq) = (v[k * m + p], v[k * m + q]);
+                    v[k * m + p] = c * vkp - s * vkq;
+                    v[k * m + q] = s * vkp + c * vkq;
+                }
+            }
+        }
+    }
+    ((0..m).map(|i| a[i * m + i]).collect(), v)
+}
+
+/// exp(-iHt) for Hermitian H from an eigen-decomposition
+///
+/// H = X + iY is embedded as the real symmetric M = [[X, -Y], [Y, X]]. For a
+/// real function f, f(M) = [[Re f(H), -Im f(H)], [Im f(H), Re f(H)]], so
+/// exp(-iHt) = cos(Ht) - i sin(Ht) follows from cos and sin of the eigenvalues.
+fn expm_hermitian_reference(h: &Mat, n: usize, t: f64) -> Mat {
+    let m = 2 * n;
+    let mut emb = vec![0.0; m * m];
+    for i in 0..n {
+        for j in 0..n {
+            let (x, y) = (h[2 * (i * n + j)], h[2 * (i * n + j) + 1]);
+            emb[i * m + j] = x;
+            emb[(i + n) * m + j + n] = x;
+            emb[i * m + j + n] = -y;
+            emb[(i + n) * m + j] = y;
+        }
+    }
+    let (vals, vecs) = jacobi_eigen(emb, m);
+    // f(M)ᵢⱼ = Σₖ Vᵢₖ f(λₖ) Vⱼₖ for f = cos(λt) and sin(λt)
+    let f = |i: usize, j: usize, g: &dyn Fn(f64) -> f64| -> f64 {
+        (0..m)
+            .map(|k| vecs[i * m + k] * g(vals[k] * t) * vecs[j * m + k])
+            .sum()
+    };
+    let mut out = [0.0; MAX_BUF];
+    for i in 0..n {
+        for j in 0..n {
+            // Block (0,0) is Re f(H), block (1,0) is Im f(H)
+            let (cos_re, cos_im) = (f(i, j, &f64::cos), f(i + n, j, &f64::cos));
+            let (sin_re, sin_im) = (f(i, j, &f64::sin), f(i + n, j, &f64::sin));
+            // cos - i·sin = (cos_re + sin_im) + i(cos_im - sin_re)
+            out[2 * (i * n + j)] = cos_re + sin_im;
+            out[2 * (i * n + j) + 1] = cos_im - sin_re;
+        }
+    }
+    out
+}
+
+/// Random Hermitian matrix with entries in [-1, 1)
+fn random_hermitian(n: usize, rng: &mut u64) -> Mat {
+    let mut h = [0.0; MAX_BUF];
+    for i in 0..n {
+        h[2 * (i * n + i)] = 2.0 * next_uniform(rng) - 1.0;
+        for j in i + 1..n {
+            let (re, im) = (2.0 * next_uniform(rng) - 1.0, 2.0 * next_uniform(rng) - 1.0);
+            h[2 * (i * n + j)] = re;
+            h[2 * (i * n + j) + 1] = im;
+            h[2 * (j * n + i)] = re;
+            h[2 * (j * n + i) + 1] = -im;
+        }
+    }
+    h
+}
+
+/// -i·t·H
+fn times_minus_i(h: &Mat, n: usize, t: f64) -> Mat {
+    let mut a = [0.0; MAX_BUF];
+    for e in 0..n * n {
+        a[2 * e] = t * h[2 * e + 1];
+        a[2 * e + 1] = -t * h[2 * e];
+    }
+    a
+}
+
+/// max |(U†U - I)ᵢⱼ|
+fn unitarity_error(u: &Mat, n: usize) -> f64 {
+    let mut err: f64 = 0.0;
+    for i in 0..n {
+        for j in 0..n {
+            let (mut re, mut im) = (0.0, 0.0);
+            for k in 0..n {
+                let (a_re, a_im) = (u[2 * (k * n + i)], -u[2 * (k * n + i) + 1]);
+                let (b_re, b_im) = (u[2 * (k * n + j)], u[2 * (k * n + j) + 1]);
+                re += a_re * b_re - a_im * b_im;
+                im += a_re * b_im + a_im * b_re;
+            }
+            let target = if i == j { 1.0 } else { 0.0 };
+            err = err.max((re - target).abs()).max(im.abs());
+        }
+    }
+    err
+}
+
+fn max_abs_diff(a: &[f64], b: &[f64]) -> f64 {
+    a.iter()
+        .zip(b)
+        .map(|(x, y)| (x - y).abs())
+        .fold(0.0, f64::max)
+}
+
+/// Deterministic pseudo-random numbers in [0, 1) (64-bit LCG)
+fn next_uniform(state: &mut u64) -> f64 {
+    *state = state
+        .wrapping_mul(6364136223846793005)
+        .wrapping_add(1442695040888963407);
+    (*state >> 11) as f64 / (1u64 << 53) as f64
+}
+
+fn main() {
+    println!("Differentiable Matrix Exponential");
+    println!("=================================\n");
+    let mut rng = 21;
+    let mut worst_value_err: f64 = 0.0;
+
+    // 1. Closed-form qubit rotation: exp(-i(n·σ)θ/2) = cos(θ/2)I - i sin(θ/2)(n·σ)
+    println!("1. Qubit rotation vs the closed form");
+    let (theta, [nx, ny, nz]) = (2.7_f64, [0.48, 0.6, 0.64]);
+    let (c, s) = ((theta / 2.0).cos(), (theta / 2.0).sin());
+    let closed = [c, -s * nz, -s * ny, -s * nx, s * ny, -s * nx, c, s * nz];
+    // A = -i(θ/2)(n·σ), n·σ = [[nz, nx - i ny], [nx + i ny, -nz]]
+    let mut a = [0.0; MAX_BUF];
+    let h = theta / 2.0;
+    let n_sigma = [nz, 0.0, nx, -ny, nx, ny, -nz, 0.0];
+    for e in 0..4 {
+        a[2 * e] = h * n_sigma[2 * e + 1];
+        a[2 * e + 1] = -h * n_sigma[2 * e];
+    }
+    for (name, u) in [("Padé", expm_pade(&a, 2)), ("Taylor", expm_taylor(&a, 2))] {
+        let err = max_abs_diff(&u[..8], &closed);
+        worst_value_err = worst_value_err.max(err);
+        println!("   {name:<7} max error = {err:.2e}");
+    }
+
+    // 2. Jordan block: exp([[λ, 1], [0, λ]]) = e^λ [[1, 1], [0, 1]]
+    println!("\n2. Non-normal Jordan block, λ = -0.3 + 2i");
+    let (l_re, l_im) = (-0.3_f64, 2.0_f64);
+    let mut jordan = [0.0; MAX_BUF];
+    jordan[..8].copy_from_slice(&[l_re, l_im, 1.0, 0.0, 0.0, 0.0, l_re, l_im]);
+    let (e_re, e_im) = (l_re.exp() * l_im.cos(), l_re.exp() * l_im.sin());
+    let expected = [e_re, e_im, e_re, e_im, 0.0, 0.0, e_re, e_im];
+    for (name, u) in [
+        ("Padé", expm_pade(&jordan, 2)),
+        ("Taylor", expm_taylor(&jordan, 2)),
+    ] {
+        let err = max_abs_diff(&u[..8], &expected);
+        worst_value_err = worst_value_err.max(err);
+        println!("   {name:<7} max error = {err:.2e}");
+    }
+
+    // 3. Hermitian H vs eigen-decomposition, including large ‖Ht‖
+    println!("\n3. exp(-iHt) for random 4×4 Hermitian H vs eigen-decomposition");
+    let n = 4;
+    let herm = random_hermitian(n, &mut rng);
+    println!(
+        "   {:>6} {:>10} {:>12} {:>12} {:>12}",
+        "t", "squarings", "Padé", "Taylor", "‖U†U - I‖"
+    );
+    for t in [0.1, 1.0, 10.0, 50.0] {
+        let a = times_minus_i(&herm, n, t);
+        let reference = expm_hermitian_reference(&herm, n, t);
+        let (pade, taylor) = (expm_pade(&a, n), expm_taylor(&a, n));
+        let (err_p, err_t) = (
+            max_abs_diff(&pade[..2 * n * n], &reference[..2 * n * n]),
+            max_abs_diff(&taylor[..2 * n * n], &reference[..2 * n * n]),
+        );
+        // exp(-iHt) is unitary, so its entries are at most 1 and the
+        // absolute error is also the relative error
+        worst_value_err = worst_value_err.max(err_p).max(err_t);
+        println!(
+            "   {t:>6} {:>10} {err_p:>12.2e} {err_t:>12.2e} {:>12.2e}",
+            scale_down(&a, n).1,
+            unitarity_error(&pade, n)
+        );
+    }
+
+    // 4. Reverse mode: gradient w.r.t. the matrix entries
+    println!("\n4. Reverse mode: ∂(Σ w·exp(A))/∂A vs finite differences (3×3 complex A)");
+    let n = 3;
+    let a: Vec<f64> = (0..2 * n * n)
+        .map(|_| 2.0 * next_uniform(&mut rng) - 1.0)
+        .collect();
+    let w: Vec<f64> = (0..2 * n * n)
+        .map(|_| 2.0 * next_uniform(&mut rng) - 1.0)
+        .collect();
+    let mut grad_p = vec![0.0; a.len()];
+    let mut grad_t = vec![0.0; a.len()];
+    d_pade_probe(&a, &mut grad_p, &w, n, 1.0);
+    d_taylor_probe(&a, &mut grad_t, &w, n, 1.0);
+    let step = 1e-6;
+    let mut fd_err: f64 = 0.0;
+    for i in 0..a.len() {
+        let mut plus = a.clone();
+        let mut minus = a.clone();
+        plus[i] += step;
+        minus[i] -= step;
+        let fd = (pade_probe(&plus, &w, n) - pade_probe(&minus, &w, n)) / (2.0 * step);
+        fd_err = fd_err.max((fd - grad_p[i]).abs());
+    }
+    let cross_err = max_abs_diff(&grad_p, &grad_t);
+    println!("   max |∇_Padé - FD|       = {fd_err:.2e}");
+    println!("   max |∇_Padé - ∇_Taylor| = {cross_err:.2e}");
+
+    // 5. Forward mode: d/dt exp(tA) = A·exp(tA)
+    println!("\n5. Forward mode: d/dt Σ w·exp(tA) vs Σ w·(A exp(tA))");
+    let t = 1.7;
+    let (_, dt_enzyme) = d_pade_time(t, 1.0, &a, &w, n);
+    let mut ta = [0.0; MAX_BUF];
+    for e in 0..2 * n * n {
+        ta[e] = t * a[e];
+    }
+    let a_exp = mat_mul(&to_mat(&a, n), &expm_pade(&ta, n), n);
+    let dt_exact: f64 = (0..2 * n * n).map(|e| w[e] * a_exp[e]).sum();
+    let time_err = (dt_enzyme - dt_exact).abs();
+    println!("   forward mode = {dt_enzyme:.12}");
+    println!("   A·exp(tA)    = {dt_exact:.12}");
+
+    println!("\n--- Verification ---");
+    if worst_value_err < 1e-12 {
+        println!("PASS: Padé and Taylor match the closed forms and the eigen-decomposition");
+    } else {
+        println!("FAIL: value error {worst_value_err:.2e}");
+    }
+    if fd_err < 1e-6 && cross_err < 1e-10 {
+        println!("PASS: reverse-mode gradients match finite differences and each other");
+    } else {
+        println!("FAIL: gradient errors {fd_err:.2e} (FD), {cross_err:.2e} (Padé vs Taylor)");
+    }
+    if time_err < 1e-10 {
+        println!("PASS: forward-mode time derivative equals A·exp(tA)");
+    } else {
+        println!("FAIL: time-derivative error {time_err:.2e}");
+    }
+}
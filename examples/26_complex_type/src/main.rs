@@ -0,0 +1,546 @@
+//! Example 26: A Differentiable Complex Number Type
+//!
+//! Examples 13 and 14 spell complex arithmetic out by hand: `(re, im)` tuples,
+//! `[f64; 4]` states and index arithmetic like `c * re0 + s * nz * im0`. This
+//! example wraps it in small `Copy` types with operator overloading:
+//!
+//! - `Complex` with `+ - * /`, `conj`, `norm_sqr` and `exp`
+//! - `CVec<N>`, a state vector with the inner product ⟨a|b⟩
+//! - `CMat<N>`, an operator with products, `dagger` and `expm`
+//!
+//! The types are plain structs of `f64`, so Enzyme differentiates straight
+//! through the operator impls. The qubit evolution of example 14 becomes
+//! `ψ = exp(-iHΔt) ψ`, and its gradient matches the index-arithmetic version.
+//!
+//! For why examples 13 and 14 are not migrated, see "Why Examples 13 and 14
+//! Keep Tuples" in the complex types chapter.
+//!
+//! Run with: RUSTFLAGS="-Z autodiff=Enable" cargo +enzyme run -p complex_type
+
+#![feature(autodiff)]
+
+use std::autodiff::autodiff_reverse;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+const N_STEPS: usize = 100;
+const DT: f64 = 1.0 / N_STEPS as f64;
+const N_ITERS: usize = 200;
+const OMEGA0: f64 = 1.0; // Drift frequency
+
+// ---------------------------------------------------------------------------
+// Enzyme-safe scalar helpers
+// ---------------------------------------------------------------------------
+
+/// Taylor series sin(x)
+fn my_sin(x: f64) -> f64 {
+    let mut sum = x;
+    let mut term = x;
+    let mut k = 1;
+    while k < 30 {
+        term *= -x * x / ((2 * k) as f64 * (2 * k + 1) as f64);
+        sum += term;
+        k += 1;
+    }
+    sum
+}
+
+/// Taylor series cos(x)
+fn my_cos(x: f64) -> f64 {
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    let mut k = 1;
+    while k < 30 {
+        term *= -x * x / ((2 * k - 1) as f64 * (2 * k) as f64);
+        sum += term;
+        k += 1;
+    }
+    sum
+}
+
+/// exp(x) via range reduction: halve until |x| ≤ 0.5, Taylor, square back
+fn my_exp(x: f64) -> f64 {
+    let mut r = x;
+    let mut halvings = 0;
+    while (if r < 0.0 { -r } else { r }) > 0.5 {
+        r /= 2.0;
+        halvings += 1;
+    }
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    let mut k = 1;
+    while k < 20 {
+        term *= r / k as f64;
+        sum += term;
+        k += 1;
+    }
+    let mut i = 0;
+    while i < halvings {
+        sum *= sum;
+        i += 1;
+    }
+    sum
+}
+
+/// Newton-Raphson sqrt
+fn my_sqrt(x: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    let mut guess = x / 2.0;
+    let mut i = 0;
+    while i < 20 {
+        guess = (guess + x / guess) / 2.0;
+        i += 1;
+    }
+    guess
+}
+
+// ---------------------------------------------------------------------------
+// Complex scalars
+// ---------------------------------------------------------------------------
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Complex {
+    re: f64,
+    im: f64,
+}
+
+impl Complex {
+    const ZERO: Complex = Complex { re: 0.0, im: 0.0 };
+    const ONE: Complex = Complex { re: 1.0, im: 0.0 };
+    const I: Complex = Complex { re: 0.0, im: 1.0 };
+
+    fn new(re: f64, im: f64) -> Self {
+        Complex { re, im }
+    }
+
+    fn conj(self) -> Self {
+        Complex::new(self.re, -self.im)
+    }
+
+    /// |z|²
+    fn norm_sqr(self) -> f64 {
+        self.re * self.re + self.im * self.im
+    }
+
+    /// exp(re)·(cos(im) + i sin(im))
+    fn exp(self) -> Self {
+        let r = my_exp(self.re);
+        Complex::new(r * my_cos(self.im), r * my_sin(self.im))
+    }
+}
+
+impl Add for Complex {
+    type Output = Complex;
+    fn add(self, rhs: Complex) -> Complex {
+        Complex::new(self.re + rhs.re, self.im + rhs.im)
+    }
+}
+
+impl Sub for Complex {
+    type Output = Complex;
+    fn sub(self, rhs: Complex) -> Complex {
+        Complex::new(self.re - rhs.re, self.im - rhs.im)
+    }
+}
+
+impl Neg for Complex {
+    type Output = Complex;
+    fn neg(self) -> Complex {
+        Complex::new(-self.re, -self.im)
+    }
+}
+
+/// (a + bi)(c + di) = (ac - bd) + (ad + bc)i
+impl Mul for Complex {
+    type Output = Complex;
+    fn mul(self, rhs: Complex) -> Complex {
+        Complex::new(
+            self.re * rhs.re - self.im * rhs.im,
+            self.re * rhs.im + self.im * rhs.re,
+        )
+    }
+}
+
+impl Mul<f64> for Complex {
+    type Output = Complex;
+    fn mul(self, rhs: f64) -> Complex {
+        Complex::new(self.re * rhs, self.im * rhs)
+    }
+}
+
+/// a / b = a·conj(b) / |b|²
+impl Div for Complex {
+    type Output = Complex;
+    fn div(self, rhs: Complex) -> Complex {
+        let d = rhs.norm_sqr();
+        let n = self * rhs.conj();
+        Complex::new(n.re / d, n.im / d)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// State vectors and operators
+// ---------------------------------------------------------------------------
+
+/// Ket |ψ⟩ in an N-dimensional Hilbert space
+#[derive(Clone, Copy, Debug)]
+struct CVec<const N: usize>([Complex; N]);
+
+impl<const N: usize> CVec<N> {
+    /// Basis state |k⟩
+    fn basis(k: usize) -> Self {
+        let mut v = [Complex::ZERO; N];
+        v[k] = Complex::ONE;
+        CVec(v)
+    }
+
+    /// ⟨self|other⟩ = Σ conj(selfᵢ)·otherᵢ
+    fn inner(&self, other: &CVec<N>) -> Complex {
+        let mut sum = Complex::ZERO;
+        let mut i = 0;
+        while i < N {
+            sum = sum + self.0[i].conj() * other.0[i];
+            i += 1;
+        }
+        sum
+    }
+}
+
+/// Operator on an N-dimensional Hilbert space, row-major
+#[derive(Clone, Copy, Debug)]
+struct CMat<const N: usize>([[Complex; N]; N]);
+
+impl<const N: usize> CMat<N> {
+    fn identity() -> Self {
+        let mut m = [[Complex::ZERO; N]; N];
+        let mut i = 0;
+        while i < N {
+            m[i][i] = Complex::ONE;
+            i += 1;
+        }
+        CMat(m)
+    }
+
+    /// Conjugate transpose A†
+    fn dagger(&self) -> Self {
+        let mut m = [[Complex::ZERO; N]; N];
+        let mut i = 0;
+        while i < N {
+            let mut j = 0;
+            while j < N {
+                m[i][j] = self.0[j][i].conj();
+                j += 1;
+            }
+            i += 1;
+        }
+        CMat(m)
+    }
+
+    /// Max row sum of |re| + |im|, an upper bound on the operator norm
+    fn norm_bound(&self) -> f64 {
+        let mut norm = 0.0;
+        let mut i = 0;
+        while i < N {
+            let mut row = 0.0;
+            let mut j = 0;
+            while j < N {
+                let z = self.0[i][j];
+                row += (if z.re < 0.0 { -z.re } else { z.re })
+                    + (if z.im < 0.0 { -z.im } else { z.im });
+                j += 1;
+            }
+            if row > norm {
+                norm = row;
+            }
+            i += 1;
+        }
+        norm
+    }
+
+    /// exp(A) by scaling and squaring with a Taylor series (see example 25)
+    fn expm(&self) -> Self {
+        let norm = self.norm_bound();
+        let mut scale = 1.0;
+        let mut squarings = 0;
+        while norm * scale > 0.5 {
+            scale /= 2.0;
+            squarings += 1;
+        }
+        let b = *self * Complex::new(scale, 0.0);
+        let mut term = CMat::identity();
+        let mut acc = CMat::identity();
+        let mut k = 1;
+        while k <= 16 {
+            term = term * b * Complex::new(1.0 / k as f64, 0.0);
+            acc = acc + term;
+            k += 1;
+        }
+        let mut s = 0;
+        while s < squarings {
+            acc = acc * acc;
+            s += 1;
+        }
+        acc
+    }
+}
+
+impl<const N: usize> Add for CMat<N> {
+    type Output = CMat<N>;
+    fn add(self, rhs: CMat<N>) -> CMat<N> {
+        let mut m = self.0;
+        let mut i = 0;
+        while i < N {
+            let mut j = 0;
+            while j < N {
+                m[i][j] = m[i][j] + rhs.0[i][j];
+                j += 1;
+            }
+            i += 1;
+        }
+        CMat(m)
+    }
+}
+
+/// Scalar multiple z·A
+impl<const N: usize> Mul<Complex> for CMat<N> {
+    type Output = CMat<N>;
+    fn mul(self, rhs: Complex) -> CMat<N> {
+        let mut m = self.0;
+        let mut i = 0;
+        while i < N {
+            let mut j = 0;
+            while j < N {
+                m[i][j] = m[i][j] * rhs;
+                j += 1;
+            }
+            i += 1;
+        }
+        CMat(m)
+    }
+}
+
+/// Operator product AB
+impl<const N: usize> Mul for CMat<N> {
+    type Output = CMat<N>;
+    fn mul(self, rhs: CMat<N>) -> CMat<N> {
+        let mut m = [[Complex::ZERO; N]; N];
+        let mut i = 0;
+        while i < N {
+            let mut j = 0;
+            while j < N {
+                let mut k = 0;
+                while k < N {
+                    m[i][j] = m[i][j] + self.0[i][k] * rhs.0[k][j];
+                    k += 1;
+                }
+                j += 1;
+            }
+            i += 1;
+        }
+        CMat(m)
+    }
+}
+
+/// Action on a ket A|ψ⟩
+impl<const N: usize> Mul<CVec<N>> for CMat<N> {
+    type Output = CVec<N>;
+    fn mul(self, rhs: CVec<N>) -> CVec<N> {
+        let mut v = [Complex::ZERO; N];
+        let mut i = 0;
+        while i < N {
+            let mut k = 0;
+            while k < N {
+                v[i] = v[i] + self.0[i][k] * rhs.0[k];
+                k += 1;
+            }
+            i += 1;
+        }
+        CVec(v)
+    }
+}
+
+fn sigma_x() -> CMat<2> {
+    CMat([[Complex::ZERO, Complex::ONE], [Complex::ONE, Complex::ZERO]])
+}
+
+fn sigma_z() -> CMat<2> {
+    CMat([
+        [Complex::ONE, Complex::ZERO],
+        [Complex::ZERO, -Complex::ONE],
+    ])
+}
+
+// ---------------------------------------------------------------------------
+// Differentiated functions
+// ---------------------------------------------------------------------------
+
+/// f(z) = |exp(z) / (1 + z²)|², exercising every operator
+#[autodiff_reverse(d_rational_exp, Active, Active, Active)]
+fn rational_exp(re: f64, im: f64) -> f64 {
+    let z = Complex::new(re, im);
+    (z.exp() / (Complex::ONE + z * z)).norm_sqr()
+}
+
+/// Example 14's X-gate infidelity, written with the complex types:
+/// H = ω₀σz/2 + Ωσx/2, ψ ← exp(-iHΔt)ψ, F = |⟨1|ψ⟩|²
+#[autodiff_reverse(d_infidelity, Duplicated, Active)]
+fn infidelity(controls: &[f64; N_STEPS]) -> f64 {
+    let mut psi = CVec::<2>::basis(0);
+    let target = CVec::<2>::basis(1);
+    let mut i = 0;
+    while i < N_STEPS {
+        let h = sigma_z() * Complex::new(OMEGA0 / 2.0, 0.0)
+            + sigma_x() * Complex::new(controls[i] / 2.0, 0.0);
+        let u = (h * (-Complex::I * DT)).expm();
+        psi = u * psi;
+        i += 1;
+    }
+    1.0 - target.inner(&psi).norm_sqr()
+}
+
+/// The same infidelity with example 14's index arithmetic on [f64; 4]
+#[autodiff_reverse(d_infidelity_arrays, Duplicated, Active)]
+fn infidelity_arrays(controls: &[f64; N_STEPS]) -> f64 {
+    let mut state = [1.0, 0.0, 0.0, 0.0];
+    let mut i = 0;
+    while i < N_STEPS {
+        let omega = controls[i];
+        let omega_eff = my_sqrt(OMEGA0 * OMEGA0 + omega * omega);
+        let theta = omega_eff * DT;
+        let nx = omega / omega_eff;
+        let nz = OMEGA0 / omega_eff;
+        let c = my_cos(theta / 2.0);
+        let s = my_sin(theta / 2.0);
+        let (re0, im0, re1, im1) = (state[0], state[1], state[2], state[3]);
+        state[0] = c * re0 + s * nz * im0 + s * nx * im1;
+        state[1] = c * im0 - s * nz * re0 - s * nx * re1;
+        state[2] = c * re1 - s * nz * im1 + s * nx * im0;
+        state[3] = c * im1 + s * nz * re1 - s * nx * re0;
+        i += 1;
+    }
+    1.0 - (state[2] * state[2] + state[3] * state[3])
+}
+
+fn max_abs_diff(a: &[f64], b: &[f64]) -> f64 {
+    a.iter()
+        .zip(b)
+        .map(|(x, y)| (x - y).abs())
+        .fold(0.0, f64::max)
+}
+
+/// Deterministic pseudo-random numbers in [0, 1) (64-bit LCG)
+fn next_uniform(state: &mut u64) -> f64 {
+    *state = state
+        .wrapping_mul(6364136223846793005)
+        .wrapping_add(1442695040888963407);
+    (*state >> 11) as f64 / (1u64 << 53) as f64
+}
+
+fn main() {
+    println!("A Differentiable Complex Number Type");
+    println!("====================================\n");
+
+    // 1. Arithmetic
+    println!("1. Operators");
+    let a = Complex::new(1.0, 2.0);
+    let b = Complex::new(3.0, -1.0);
+    let (sum, prod, quot) = (a + b, a * b, a / b);
+    println!("   a = 1 + 2i, b = 3 - i");
+    println!("   a + b = {:.4} + {:.4}i", sum.re, sum.im);
+    println!("   a · b = {:.4} + {:.4}i", prod.re, prod.im);
+    println!("   a / b = {:.4} + {:.4}i", quot.re, quot.im);
+    let euler = (Complex::I * std::f64::consts::PI).exp() + Complex::ONE;
+    println!("   exp(iπ) + 1 = {:.1e} + {:.1e}i", euler.re, euler.im);
+    let arith_err = (quot * b - a)
+        .norm_sqr()
+        .sqrt()
+        .max(euler.norm_sqr().sqrt());
+
+    // 2. Gradient through the operators
+    println!("\n2. f(z) = |exp(z) / (1 + z²)|² at z = 0.3 + 0.7i");
+    let (re, im) = (0.3, 0.7);
+    let (f, d_re, d_im) = d_rational_exp(re, im, 1.0);
+    let step = 1e-6;
+    let fd_re = (rational_exp(re + step, im) - rational_exp(re - step, im)) / (2.0 * step);
+    let fd_im = (rational_exp(re, im + step) - rational_exp(re, im - step)) / (2.0 * step);
+    println!("   f = {f:.10}");
+    println!("   ∂f/∂re = {d_re:.10} (FD: {fd_re:.10})");
+    println!("   ∂f/∂im = {d_im:.10} (FD: {fd_im:.10})");
+    let scalar_err = (d_re - fd_re).abs().max((d_im - fd_im).abs());
+
+    // 3. Qubit evolution: operator form vs index arithmetic
+    println!("\n3. Qubit evolution ψ ← exp(-iHΔt)ψ vs example 14's index arithmetic");
+    let mut rng = 14;
+    let mut controls = [0.0; N_STEPS];
+    for c in controls.iter_mut() {
+        *c = 8.0 * next_uniform(&mut rng) - 4.0;
+    }
+    let mut grad_types = [0.0; N_STEPS];
+    let mut grad_arrays = [0.0; N_STEPS];
+    let j_types = d_infidelity(&controls, &mut grad_types, 1.0);
+    let j_arrays = d_infidelity_arrays(&controls, &mut grad_arrays, 1.0);
+    let evolution_err = (j_types - j_arrays).abs();
+    let grad_err = max_abs_diff(&grad_types, &grad_arrays);
+    println!("   infidelity: types {j_types:.12}, arrays {j_arrays:.12}");
+    println!("   max |∇_types - ∇_arrays| = {grad_err:.2e}");
+
+    // Each exp(-iHΔt) is unitary: U†U = I
+    let h = sigma_z() * Complex::new(OMEGA0 / 2.0, 0.0) + sigma_x() * Complex::new(3.0, 0.0);
+    let u = (h * (-Complex::I * 0.7)).expm();
+    let uu = u.dagger() * u;
+    let unitarity_err = (0..2)
+        .flat_map(|i| (0..2).map(move |j| (i, j)))
+        .map(|(i, j)| {
+            (uu.0[i][j] - CMat::<2>::identity().0[i][j])
+                .norm_sqr()
+                .sqrt()
+        })
+        .fold(0.0, f64::max);
+    println!("   ‖U†U - I‖ = {unitarity_err:.2e}");
+
+    // 4. Optimize with the typed version
+    println!("\n4. Adam on the typed infidelity");
+    let mut controls = [1.0; N_STEPS];
+    let mut m = [0.0; N_STEPS];
+    let mut v = [0.0; N_STEPS];
+    let (beta1, beta2, epsilon, lr) = (0.9, 0.999, 1e-8, 0.3);
+    for iter in 0..N_ITERS {
+        let mut grad = [0.0; N_STEPS];
+        let infid = d_infidelity(&controls, &mut grad, 1.0);
+        for i in 0..N_STEPS {
+            m[i] = beta1 * m[i] + (1.0 - beta1) * grad[i];
+            v[i] = beta2 * v[i] + (1.0 - beta2) * grad[i] * grad[i];
+            let m_hat = m[i] / (1.0 - beta1.powi(iter as i32 + 1));
+            let v_hat = v[i] / (1.0 - beta2.powi(iter as i32 + 1));
+            controls[i] -= lr * m_hat / (v_hat.sqrt() + epsilon);
+        }
+        if iter % 50 == 0 {
+            println!("   Iter {iter:4}: fidelity = {:.10}", 1.0 - infid);
+        }
+    }
+    let fidelity = 1.0 - infidelity(&controls);
+    println!("   Final:     fidelity = {fidelity:.10}");
+
+    println!("\n--- Verification ---");
+    if arith_err < 1e-12 {
+        println!("PASS: (a/b)·b = a and exp(iπ) = -1");
+    } else {
+        println!("FAIL: arithmetic error {arith_err:.2e}");
+    }
+    if scalar_err < 1e-6 {
+        println!("PASS: gradient through the operators matches finite differences");
+    } else {
+        println!("FAIL: scalar gradient error {scalar_err:.2e}");
+    }
+    if evolution_err < 1e-12 && grad_err < 1e-10 && unitarity_err < 1e-12 {
+        println!("PASS: typed evolution matches index arithmetic in value and gradient");
+    } else {
+        println!("FAIL: evolution {evolution_err:.2e}, gradient {grad_err:.2e}, unitarity {unitarity_err:.2e}");
+    }
+    if fidelity > 0.9999 {
+        println!("SUCCESS: Achieved >99.99% fidelity with the typed version");
+    } else {
+        println!("FAIL: fidelity {fidelity:.6}");
+    }
+}
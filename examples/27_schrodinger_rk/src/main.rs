@@ -0,0 +1,484 @@
+//! Example 27: Runge-Kutta Schrödinger Integrators
+//!
+//! Example 14 evolves the qubit with exact piecewise-constant steps
+//! exp(-iHΔt). With a smoothly varying control Ω(t), sampling it once per step
+//! is itself a discretization error. This example integrates
+//!
+//!   dψ/dt = -i H(t) ψ,   H(t) = ω₀σz/2 + Ω(t)σx/2
+//!
+//! directly, evaluating Ω at every Runge-Kutta stage:
+//!
+//! - RK4 with a fixed number of steps
+//! - Adaptive Dormand-Prince RK45 with step doubling and halving
+//!
+//! Both are differentiable end-to-end w.r.t. the pulse coefficients. The
+//! example measures the discretization error of each method, including
+//! `apply_exact_step`, and optimizes the pulse through the adaptive solver.
+//!
+//! Run with: RUSTFLAGS="-Z autodiff=Enable" cargo +enzyme run -p schrodinger_rk
+
+#![feature(autodiff)]
+
+use std::autodiff::autodiff_reverse;
+
+const OMEGA0: f64 = 1.0; // Drift frequency
+const T_FINAL: f64 = 1.0;
+/// Fourier modes in Ω(t) = Σₖ pₖ sin((k+1)πt/T)
+const N_MODES: usize = 4;
+const N_ITERS: usize = 300;
+/// Safety cap on adaptive steps (accepted + rejected)
+const MAX_ATTEMPTS: usize = 100_000;
+
+/// Taylor series sin(x) - high precision
+fn my_sin(x: f64) -> f64 {
+    let mut sum = x;
+    let mut term = x;
+    let mut k = 1;
+    while k < 30 {
+        term *= -x * x / ((2 * k) as f64 * (2 * k + 1) as f64);
+        sum += term;
+        k += 1;
+    }
+    sum
+}
+
+/// Taylor series cos(x) - high precision
+fn my_cos(x: f64) -> f64 {
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    let mut k = 1;
+    while k < 30 {
+        term *= -x * x / ((2 * k - 1) as f64 * (2 * k) as f64);
+        sum += term;
+        k += 1;
+    }
+    sum
+}
+
+/// Newton-Raphson sqrt
+fn my_sqrt(x: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    let mut guess = x / 2.0;
+    let mut i = 0;
+    while i < 20 {
+        guess = (guess + x / guess) / 2.0;
+        i += 1;
+    }
+    guess
+}
+
+/// Smooth control Ω(t) = Σₖ pₖ sin((k+1)πt/T), zero at both ends
+fn control(params: &[f64], t: f64) -> f64 {
+    let mut omega = 0.0;
+    let mut k = 0;
+    while k < N_MODES {
+        omega += params[k] * my_sin((k + 1) as f64 * std::f64::consts::PI * t / T_FINAL);
+        k += 1;
+    }
+    omega
+}
+
+/// dψ/dt = -i(bσz + aσx)ψ with a = Ω/2, b = ω₀/2
+///
+/// State: [Re(c0), Im(c0), Re(c1), Im(c1)]; -i(x + iy) = y - ix
+fn rhs(psi: &[f64; 4], omega: f64) -> [f64; 4] {
+    let (a, b) = (omega / 2.0, OMEGA0 / 2.0);
+    // Hψ
+    let h0_re = b * psi[0] + a * psi[2];
+    let h0_im = b * psi[1] + a * psi[3];
+    let h1_re = a * psi[0] - b * psi[2];
+    let h1_im = a * psi[1] - b * psi[3];
+    [h0_im, -h0_re, h1_im, -h1_re]
+}
+
+/// ψ + h Σᵢ cᵢ kᵢ over the first `n` stages
+fn combine(psi: &[f64; 4], k: &[[f64; 4]; 7], coeffs: &[f64; 7], n: usize, h: f64) -> [f64; 4] {
+    let mut out = *psi;
+    let mut i = 0;
+    while i < n {
+        let mut e = 0;
+        while e < 4 {
+            out[e] += h * coeffs[i] * k[i][e];
+            e += 1;
+        }
+        i += 1;
+    }
+    out
+}
+
+/// Classic RK4 step; `omegas` are Ω at t, t + h/2 and t + h
+fn rk4_step(psi: &[f64; 4], omegas: [f64; 3], h: f64) -> [f64; 4] {
+    let k1 = rhs(psi, omegas[0]);
+    let mut tmp = [0.0; 4];
+    let mut e = 0;
+    while e < 4 {
+        tmp[e] = psi[e] + 0.5 * h * k1[e];
+        e += 1;
+    }
+    let k2 = rhs(&tmp, omegas[1]);
+    let mut e = 0;
+    while e < 4 {
+        tmp[e] = psi[e] + 0.5 * h * k2[e];
+        e += 1;
+    }
+    let k3 = rhs(&tmp, omegas[1]);
+    let mut e = 0;
+    while e < 4 {
+        tmp[e] = psi[e] + h * k3[e];
+        e += 1;
+    }
+    let k4 = rhs(&tmp, omegas[2]);
+    let mut out = *psi;
+    let mut e = 0;
+    while e < 4 {
+        out[e] += h / 6.0 * (k1[e] + 2.0 * k2[e] + 2.0 * k3[e] + k4[e]);
+        e += 1;
+    }
+    out
+}
+
+/// RK4 over [0, T] in `n_steps` equal steps
+fn evolve_rk4(params: &[f64], n_steps: usize) -> [f64; 4] {
+    let h = T_FINAL / n_steps as f64;
+    let mut psi = [1.0, 0.0, 0.0, 0.0];
+    let mut i = 0;
+    while i < n_steps {
+        let t = i as f64 * h;
+        let omegas = [
+            control(params, t),
+            control(params, t + 0.5 * h),
+            control(params, t + h),
+        ];
+        psi = rk4_step(&psi, omegas, h);
+        i += 1;
+    }
+    psi
+}
+
+// Dormand-Prince 5(4) tableau
+const DP_C: [f64; 7] = [0.0, 1.0 / 5.0, 3.0 / 10.0, 4.0 / 5.0, 8.0 / 9.0, 1.0, 1.0];
+const DP_A: [[f64; 7]; 7] = [
+    [0.0; 7],
+    [1.0 / 5.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+    [3.0 / 40.0, 9.0 / 40.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+    [44.0 / 45.0, -56.0 / 15.0, 32.0 / 9.0, 0.0, 0.0, 0.0, 0.0],
+    [
+        19372.0 / 6561.0,
+        -25360.0 / 2187.0,
+        64448.0 / 6561.0,
+        -212.0 / 729.0,
+        0.0,
+        0.0,
+        0.0,
+    ],
+    [
+        9017.0 / 3168.0,
+        -355.0 / 33.0,
+        46732.0 / 5247.0,
+        49.0 / 176.0,
+        -5103.0 / 18656.0,
+        0.0,
+        0.0,
+    ],
+    [
+        35.0 / 384.0,
+        0.0,
+        500.0 / 1113.0,
+        125.0 / 192.0,
+        -2187.0 / 6784.0,
+        11.0 / 84.0,
+        0.0,
+    ],
+];
+/// 5th-order weights (the last row of A)
+const DP_B5: [f64; 7] = [
+    35.0 / 384.0,
+    0.0,
+    500.0 / 1113.0,
+    125.0 / 192.0,
+    -2187.0 / 6784.0,
+    11.0 / 84.0,
+    0.0,
+];
+/// Embedded 4th-order weights
+const DP_B4: [f64; 7] = [
+    5179.0 / 57600.0,
+    0.0,
+    7571.0 / 16695.0,
+    393.0 / 640.0,
+    -92097.0 / 339200.0,
+    187.0 / 2100.0,
+    1.0 / 40.0,
+];
+
+/// One Dormand-Prince step from t; returns (5th-order ψ, max |ψ₅ - ψ₄|)
+fn dp45_step(params: &[f64], psi: &[f64; 4], t: f64, h: f64) -> ([f64; 4], f64) {
+    let mut k = [[0.0; 4]; 7];
+    let mut i = 0;
+    while i < 7 {
+        let stage = combine(psi, &k, &DP_A[i], i, h);
+        k[i] = rhs(&stage, control(params, t + DP_C[i] * h));
+        i += 1;
+    }
+    let high = combine(psi, &k, &DP_B5, 7, h);
+    let low = combine(psi, &k, &DP_B4, 7, h);
+    let mut err = 0.0;
+    let mut e = 0;
+    while e < 4 {
+        let d = high[e] - low[e];
+        let d = if d < 0.0 { -d } else { d };
+        if d > err {
+            err = d;
+        }
+        e += 1;
+    }
+    (high, err)
+}
+
+/// Adaptive RK45 over [0, T]; returns (ψ(T), accepted steps, rejected steps)
+///
+/// Steps are only ever halved (error > tol) or doubled (error < tol/32). The
+/// mesh is therefore piecewise constant in the parameters, and the gradient is
+/// that of the discrete solution on the mesh the solver chose. Panics if the
+/// step cap `MAX_ATTEMPTS` runs out before T is reached.
+fn evolve_rk45(params: &[f64], tol: f64) -> ([f64; 4], usize, usize) {
+    let mut psi = [1.0, 0.0, 0.0, 0.0];
+    let mut t = 0.0;
+    let mut h = T_FINAL / 16.0;
+    let mut accepted = 0;
+    let mut rejected = 0;
+    while t < T_FINAL && accepted + rejected < MAX_ATTEMPTS {
+        let step = if t + h > T_FINAL { T_FINAL - t } else { h };
+        let (next, err) = dp45_step(params, &psi, t, step);
+        if err <= tol {
+            psi = next;
+            t += step;
+            accepted += 1;
+            if err < tol / 32.0 {
+                h *= 2.0;
+            }
+        } else {
+            h /= 2.0;
+            rejected += 1;
+        }
+    }
+    assert!(
+        t >= T_FINAL,
+        "RK45 stopped at t = {t} before T = {T_FINAL} after {MAX_ATTEMPTS} attempts"
+    );
+    (psi, accepted, rejected)
+}
+
+/// Target |1⟩ up to a global phase: 1 - |⟨1|ψ⟩|²
+fn infidelity_of(psi: &[f64; 4]) -> f64 {
+    1.0 - (psi[2] * psi[2] + psi[3] * psi[3])
+}
+
+#[autodiff_reverse(d_infidelity_rk4, Duplicated, Const, Active)]
+fn infidelity_rk4(params: &[f64], n_steps: usize) -> f64 {
+    infidelity_of(&evolve_rk4(params, n_steps))
+}
+
+#[autodiff_reverse(d_infidelity_rk45, Duplicated, Const, Active)]
+fn infidelity_rk45(params: &[f64], tol: f64) -> f64 {
+    infidelity_of(&evolve_rk45(params, tol).0)
+}
+
+// ---------------------------------------------------------------------------
+// Exact piecewise-constant steps (plain Rust, not differentiated)
+// ---------------------------------------------------------------------------
+
+/// Exact time evolution: U = exp(-i(ω₀σz/2 + Ωσx/2)Δt), as in example 14
+fn apply_exact_step(state: &mut [f64; 4], omega: f64, dt: f64) {
+    let omega_eff = my_sqrt(OMEGA0 * OMEGA0 + omega * omega);
+    let theta = omega_eff * dt;
+
+    // Normalized rotation axis: n = (omega, 0, omega0) / omega_eff
+    let nx = omega / omega_eff;
+    let nz = OMEGA0 / omega_eff;
+
+    let c = my_cos(theta / 2.0);
+    let s = my_sin(theta / 2.0);
+
+    let (re0, im0, re1, im1) = (state[0], state[1], state[2], state[3]);
+    state[0] = c * re0 + s * nz * im0 + s * nx * im1;
+    state[1] = c * im0 - s * nz * re0 - s * nx * re1;
+    state[2] = c * re1 - s * nz * im1 + s * nx * im0;
+    state[3] = c * im1 + s * nz * re1 - s * nx * re0;
+}
+
+/// Exact steps with Ω sampled at each step's midpoint
+fn evolve_exact_midpoint(params: &[f64], n_steps: usize) -> [f64; 4] {
+    let h = T_FINAL / n_steps as f64;
+    let mut psi = [1.0, 0.0, 0.0, 0.0];
+    for i in 0..n_steps {
+        apply_exact_step(&mut psi, control(params, (i as f64 + 0.5) * h), h);
+    }
+    psi
+}
+
+fn state_error(a: &[f64; 4], b: &[f64; 4]) -> f64 {
+    a.iter()
+        .zip(b)
+        .map(|(x, y)| (x - y).abs())
+        .fold(0.0, f64::max)
+}
+
+/// Deterministic pseudo-random numbers in [0, 1) (64-bit LCG)
+fn next_uniform(state: &mut u64) -> f64 {
+    *state = state
+        .wrapping_mul(6364136223846793005)
+        .wrapping_add(1442695040888963407);
+    (*state >> 11) as f64 / (1u64 << 53) as f64
+}
+
+fn main() {
+    println!("Runge-Kutta Schrödinger Integrators");
+    println!("===================================\n");
+    let mut rng = 27;
+
+    // 1. Piecewise-constant controls: apply_exact_step is exact, so the
+    //    difference is RK4's own truncation error
+    println!("1. RK4 vs apply_exact_step on 20 piecewise-constant controls");
+    let pieces: Vec<f64> = (0..20)
+        .map(|_| 8.0 * next_uniform(&mut rng) - 4.0)
+        .collect();
+    let dt = T_FINAL / pieces.len() as f64;
+    let mut exact = [1.0, 0.0, 0.0, 0.0];
+    for &omega in &pieces {
+        apply_exact_step(&mut exact, omega, dt);
+    }
+    println!("   {:>9} {:>12} {:>8}", "substeps", "error", "ratio");
+    let mut last_err = f64::NAN;
+    let mut last_ratio = 0.0;
+    for substeps in [1, 2, 4, 8, 16] {
+        let h = dt / substeps as f64;
+        let mut psi = [1.0, 0.0, 0.0, 0.0];
+        for &omega in &pieces {
+            for _ in 0..substeps {
+                psi = rk4_step(&psi, [omega; 3], h);
+            }
+        }
+        let err = state_error(&psi, &exact);
+        last_ratio = last_err / err;
+        println!("   {substeps:>9} {err:>12.3e} {last_ratio:>8.1}");
+        last_err = err;
+    }
+
+    // 2. Smooth control: compare against a converged RK4 reference
+    let params: Vec<f64> = (0..N_MODES)
+        .map(|_| 6.0 * next_uniform(&mut rng) - 3.0)
+        .collect();
+    let reference = evolve_rk4(&params, 20_000);
+    println!("\n2. Smooth control Ω(t) = Σ pₖ sin((k+1)πt/T), error vs RK4 with 20000 steps");
+    println!(
+        "   {:>6} {:>16} {:>12} {:>12}",
+        "steps", "exact midpoint", "RK4", "RK4 evals"
+    );
+    for n in [25, 50, 100, 200, 400] {
+        let err_exact = state_error(&evolve_exact_midpoint(&params, n), &reference);
+        let err_rk4 = state_error(&evolve_rk4(&params, n), &reference);
+        println!(
+            "   {n:>6} {err_exact:>16.3e} {err_rk4:>12.3e} {:>12}",
+            4 * n
+        );
+    }
+    println!("   The exact step is exact for constant Ω, but sampling a smooth Ω");
+    println!("   once per step leaves a second-order error. RK4 is fourth order.");
+
+    println!("\n   Adaptive RK45:");
+    println!(
+        "   {:>8} {:>12} {:>9} {:>9} {:>8}",
+        "tol", "error", "accepted", "rejected", "evals"
+    );
+    let mut rk45_ok = true;
+    for tol in [1e-4, 1e-6, 1e-8, 1e-10] {
+        let (psi, accepted, rejected) = evolve_rk45(&params, tol);
+        let err = state_error(&psi, &reference);
+        rk45_ok &= err < 10.0 * tol;
+        println!(
+            "   {tol:>8.0e} {err:>12.3e} {accepted:>9} {rejected:>9} {:>8}",
+            7 * (accepted + rejected)
+        );
+    }
+
+    // 3. Gradients through both integrators
+    println!("\n3. Gradient of 1 - |⟨1|ψ(T)⟩|² w.r.t. the Fourier coefficients");
+    let tol = 1e-10;
+    let mut grad_rk4 = vec![0.0; N_MODES];
+    let mut grad_rk45 = vec![0.0; N_MODES];
+    d_infidelity_rk4(&params, &mut grad_rk4, 400, 1.0);
+    d_infidelity_rk45(&params, &mut grad_rk45, tol, 1.0);
+    let step = 1e-6;
+    let mut fd_err: f64 = 0.0;
+    println!(
+        "   {:>3} {:>14} {:>14} {:>14}",
+        "k", "RK4", "RK45", "FD (RK45)"
+    );
+    for k in 0..N_MODES {
+        let mut plus = params.clone();
+        let mut minus = params.clone();
+        plus[k] += step;
+        minus[k] -= step;
+        let fd = (infidelity_rk45(&plus, tol) - infidelity_rk45(&minus, tol)) / (2.0 * step);
+        fd_err = fd_err.max((fd - grad_rk45[k]).abs());
+        println!(
+            "   {k:>3} {:>14.9} {:>14.9} {fd:>14.9}",
+            grad_rk4[k], grad_rk45[k]
+        );
+    }
+    let method_gap = grad_rk4
+        .iter()
+        .zip(&grad_rk45)
+        .map(|(a, b)| (a - b).abs())
+        .fold(0.0, f64::max);
+
+    // 4. Optimize the smooth pulse through the adaptive solver
+    println!("\n4. Adam on the Fourier coefficients through RK45 (tol = {tol:.0e})");
+    let mut p = vec![1.0; N_MODES];
+    let mut m = [0.0; N_MODES];
+    let mut v = [0.0; N_MODES];
+    let (beta1, beta2, epsilon, lr) = (0.9, 0.999, 1e-8, 0.1);
+    for iter in 0..N_ITERS {
+        let mut grad = vec![0.0; N_MODES];
+        let infid = d_infidelity_rk45(&p, &mut grad, tol, 1.0);
+        for k in 0..N_MODES {
+            m[k] = beta1 * m[k] + (1.0 - beta1) * grad[k];
+            v[k] = beta2 * v[k] + (1.0 - beta2) * grad[k] * grad[k];
+            let m_hat = m[k] / (1.0 - beta1.powi(iter as i32 + 1));
+            let v_hat = v[k] / (1.0 - beta2.powi(iter as i32 + 1));
+            p[k] -= lr * m_hat / (v_hat.sqrt() + epsilon);
+        }
+        if iter % 50 == 0 {
+            println!("   Iter {iter:4}: fidelity = {:.10}", 1.0 - infid);
+        }
+    }
+    let fidelity = 1.0 - infidelity_rk45(&p, tol);
+    println!("   Final:     fidelity = {fidelity:.10}");
+    let check = 1.0 - infidelity_of(&evolve_exact_midpoint(&p, 20_000));
+    println!("   Same pulse, 20000 exact midpoint steps: fidelity = {check:.10}");
+
+    println!("\n--- Verification ---");
+    if (14.0..18.0).contains(&last_ratio) {
+        println!("PASS: RK4 error drops 16× per halving (fourth order)");
+    } else {
+        println!("FAIL: RK4 convergence ratio {last_ratio:.1}");
+    }
+    if rk45_ok {
+        println!("PASS: RK45 global error within 10× the tolerance");
+    } else {
+        println!("FAIL: RK45 missed its tolerance");
+    }
+    if fd_err < 1e-6 && method_gap < 1e-6 {
+        println!("PASS: RK45 gradient matches finite differences and RK4");
+    } else {
+        println!("FAIL: gradient errors {fd_err:.2e} (FD), {method_gap:.2e} (RK4 vs RK45)");
+    }
+    if fidelity > 0.9999 && (fidelity - check).abs() < 1e-6 {
+        println!("SUCCESS: Smooth pulse reaches >99.99% fidelity, confirmed by exact steps");
+    } else {
+        println!("FAIL: fidelity {fidelity:.6} (exact-step check {check:.6})");
+    }
+}
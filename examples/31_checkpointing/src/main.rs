@@ -0,0 +1,413 @@
+//! Example 31: Checkpointing Long Time Loops
+//!
+//! Reverse mode through `infidelity_steps` (example 14) keeps every
+//! intermediate state for the backward pass, so memory grows linearly with the
+//! number of steps. Binomial checkpointing (Griewank's Revolve) stores at most
+//! a fixed budget of states and recomputes the segments in between during the
+//! backward pass: memory becomes O(budget), at the price of each step being
+//! evaluated a few extra times.
+//!
+//! `checkpointed_gradient` wraps any step-function loop x ← step(x, uₖ). Enzyme
+//! differentiates a single step, as a vector-Jacobian product; the wrapper
+//! schedules the forward recomputations and the reverse sweep.
+//!
+//! Run with: RUSTFLAGS="-Z autodiff=Enable" cargo +enzyme run -p checkpointing
+
+#![feature(autodiff)]
+
+use std::autodiff::autodiff_reverse;
+
+const OMEGA0: f64 = 1.0; // Drift frequency
+/// Steps in the long time loop
+const N_LONG: usize = 100_000;
+
+/// Taylor series sin(x) - high precision
+fn my_sin(x: f64) -> f64 {
+    let mut sum = x;
+    let mut term = x;
+    let mut k = 1;
+    while k < 30 {
+        term *= -x * x / ((2 * k) as f64 * (2 * k + 1) as f64);
+        sum += term;
+        k += 1;
+    }
+    sum
+}
+
+/// Taylor series cos(x) - high precision
+fn my_cos(x: f64) -> f64 {
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    let mut k = 1;
+    while k < 30 {
+        term *= -x * x / ((2 * k - 1) as f64 * (2 * k) as f64);
+        sum += term;
+        k += 1;
+    }
+    sum
+}
+
+/// Newton-Raphson sqrt
+fn my_sqrt(x: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    let mut guess = x / 2.0;
+    let mut i = 0;
+    while i < 20 {
+        guess = (guess + x / guess) / 2.0;
+        i += 1;
+    }
+    guess
+}
+
+/// Exact time evolution: U = exp(-i(ω₀σz/2 + Ωσx/2)Δt), as in example 14
+fn apply_exact_step(state: &mut [f64; 4], omega: f64, dt: f64) {
+    let omega_eff = my_sqrt(OMEGA0 * OMEGA0 + omega * omega);
+    let theta = omega_eff * dt;
+
+    // Normalized rotation axis: n = (omega, 0, omega0) / omega_eff
+    let nx = omega / omega_eff;
+    let nz = OMEGA0 / omega_eff;
+
+    let c = my_cos(theta / 2.0);
+    let s = my_sin(theta / 2.0);
+
+    let (re0, im0, re1, im1) = (state[0], state[1], state[2], state[3]);
+
+    state[0] = c * re0 + s * nz * im0 + s * nx * im1;
+    state[1] = c * im0 - s * nz * re0 - s * nx * re1;
+    state[2] = c * re1 - s * nz * im1 + s * nx * im0;
+    state[3] = c * im1 + s * nz * re1 - s * nx * re0;
+}
+
+/// Target |1⟩ up to a global phase: 1 - |⟨1|ψ⟩|²
+fn infidelity_of(state: &[f64; 4]) -> f64 {
+    1.0 - (state[2] * state[2] + state[3] * state[3])
+}
+
+/// Unrolled baseline as in example 14: Enzyme records every step
+#[autodiff_reverse(d_infidelity_steps, Duplicated, Const, Active)]
+fn infidelity_steps(controls: &[f64], dt: f64) -> f64 {
+    let mut state = [1.0, 0.0, 0.0, 0.0];
+    let mut i = 0;
+    while i < controls.len() {
+        apply_exact_step(&mut state, controls[i], dt);
+        i += 1;
+    }
+    infidelity_of(&state)
+}
+
+/// λ·U(Ω)ψ; its gradient is (Uᵀλ, λ·∂(Uψ)/∂Ω), the VJP of one step
+#[autodiff_reverse(d_step_probe, Duplicated, Active, Const, Const, Active)]
+fn step_probe(state: &[f64; 4], omega: f64, dt: f64, lambda: &[f64; 4]) -> f64 {
+    let mut out = *state;
+    apply_exact_step(&mut out, omega, dt);
+    lambda[0] * out[0] + lambda[1] * out[1] + lambda[2] * out[2] + lambda[3] * out[3]
+}
+
+// ---------------------------------------------------------------------------
+// Binomial checkpointing (plain Rust around the Enzyme step VJP)
+// ---------------------------------------------------------------------------
+
+/// x ← step(x, u)
+type Step<'a, const D: usize> = &'a dyn Fn(&mut [f64; D], f64);
+/// (x, u, λ) -> (λᵀ∂step/∂x, λᵀ∂step/∂u) at the state x before the step
+type StepVjp<'a, const D: usize> = &'a dyn Fn(&[f64; D], f64, &[f64; D]) -> ([f64; D], f64);
+/// x_N -> (L, ∂L/∂x_N)
+type TerminalLoss<'a, const D: usize> = &'a dyn Fn(&[f64; D]) -> (f64, [f64; D]);
+
+/// Work and memory of one checkpointed gradient
+#[derive(Debug, Clone, Copy)]
+struct CheckpointStats {
+    /// Step evaluations, including recomputation (the loop itself has N)
+    forward_steps: usize,
+    /// Most states held at once, including x₀
+    peak_states: usize,
+}
+
+/// β(s, r) = C(s + r, s): the most steps s stored states can reverse when each
+/// step is evaluated at most r times. Saturates instead of overflowing.
+fn beta(s: usize, r: usize) -> usize {
+    let mut b: usize = 1;
+    for i in 1..=r {
+        b = match b.checked_mul(s + i) {
+            Some(v) => v / i,
+            None => return usize::MAX,
+        };
+    }
+    b
+}
+
+/// Fewest evaluations per step r with β(s, r) ≥ steps
+fn repetitions(s: usize, steps: usize) -> usize {
+    let mut r = 0;
+    while beta(s, r) < steps {
+        r += 1;
+    }
+    r
+}
+
+/// Fewest step evaluations any schedule needs to reverse `steps` steps with
+/// `snapshots` stored states: r·l - β(s + 1, r - 1), plus the last step to x_N.
+/// Needs steps ≥ 2.
+fn optimal_forward_steps(snapshots: usize, steps: usize) -> usize {
+    let r = repetitions(snapshots, steps);
+    r * steps - beta(snapshots + 1, r - 1) + 1
+}
+
+/// Offset of the next checkpoint when reversing l ≥ 2 steps from a stored
+/// state with s snapshots (that state plus the free slots). This is the
+/// binomial split of Griewank and Walther's Revolve.
+fn revolve_split(s: usize, l: usize) -> usize {
+    let r = repetitions(s, l);
+    let b1 = beta(s, r - 1);
+    let b2 = beta(s - 1, r - 1);
+    let b3 = if s == 1 { 0 } else { beta(s - 2, r - 1) };
+    let b4 = if r >= 2 { beta(s, r - 2) } else { 0 };
+    let b5 = if s < 3 { 0 } else { beta(s - 3, r) };
+    let m = if l <= b1 + b3 {
+        b4
+    } else if l >= beta(s, r) - b5 {
+        b1
+    } else {
+        l - b2 - b3
+    };
+    m.clamp(1, l - 1)
+}
+
+/// Gradient of L(x_N) w.r.t. the controls of the loop x_{k+1} = step(x_k, u_k),
+/// holding at most `budget` states (≥ 1, counting x₀).
+///
+/// The checkpoints form a stack. To reverse steps [a, end) from the top
+/// checkpoint x_a, the next checkpoint goes at the `revolve_split` offset,
+/// which minimizes the total number of step evaluations. With no free slot,
+/// x_{end-1} is recomputed from x_a. A budget of N + 1 stores every state,
+/// like plain reverse mode.
+///
+/// Writes dL/du into `grad` (at least as long as `controls`) and returns
+/// (L, stats). With no controls, L is the loss at x₀.
+fn checkpointed_gradient<const D: usize>(
+    step: Step<D>,
+    step_vjp: StepVjp<D>,
+    loss: TerminalLoss<D>,
+    x0: &[f64; D],
+    controls: &[f64],
+    budget: usize,
+    grad: &mut [f64],
+) -> (f64, CheckpointStats) {
+    let n = controls.len();
+    assert!(
+        grad.len() >= n,
+        "grad has {} entries for {n} controls",
+        grad.len()
+    );
+    let budget = budget.max(1);
+    let mut stats = CheckpointStats {
+        forward_steps: 0,
+        peak_states: 1,
+    };
+    if n == 0 {
+        return (loss(x0).0, stats);
+    }
+    let advance = |x: &mut [f64; D], from: usize, to: usize| {
+        for &u in &controls[from..to] {
+            step(x, u);
+        }
+        to - from
+    };
+    let mut stack = vec![(0, *x0)];
+    let mut value = 0.0;
+    let mut lambda: Option<[f64; D]> = None;
+    let mut end = n;
+    while end > 0 {
+        let (a, x_a) = *stack.last().unwrap();
+        let free = budget - stack.len();
+        if a + 1 < end && free > 0 {
+            let m = a + revolve_split(free + 1, end - a);
+            let mut x = x_a;
+            stats.forward_steps += advance(&mut x, a, m);
+            stack.push((m, x));
+            stats.peak_states = stats.peak_states.max(stack.len());
+            continue;
+        }
+
+        // Reverse step end - 1 from x_{end-1}
+        let mut x_prev = x_a;
+        stats.forward_steps += advance(&mut x_prev, a, end - 1);
+        let lambda_out = match lambda {
+            Some(l) => l,
+            None => {
+                let mut x_n = x_prev;
+                stats.forward_steps += advance(&mut x_n, n - 1, n);
+                let (l_value, dl_dx) = loss(&x_n);
+                value = l_value;
+                dl_dx
+            }
+        };
+        let (lambda_in, du) = step_vjp(&x_prev, controls[end - 1], &lambda_out);
+        grad[end - 1] = du;
+        lambda = Some(lambda_in);
+        if a == end - 1 {
+            stack.pop();
+        }
+        end -= 1;
+    }
+    (value, stats)
+}
+
+// ---------------------------------------------------------------------------
+// Measurements (plain Rust, not differentiated)
+// ---------------------------------------------------------------------------
+
+/// Average wall time of `f` over enough repetitions to fill ~50 ms
+fn time_per_call(mut f: impl FnMut()) -> f64 {
+    let mut reps = 1;
+    loop {
+        let start = std::time::Instant::now();
+        for _ in 0..reps {
+            f();
+        }
+        let elapsed = start.elapsed().as_secs_f64();
+        if elapsed > 0.05 {
+            return elapsed / reps as f64;
+        }
+        reps *= 2;
+    }
+}
+
+fn max_abs_diff(a: &[f64], b: &[f64]) -> f64 {
+    a.iter()
+        .zip(b)
+        .map(|(x, y)| (x - y).abs())
+        .fold(0.0, f64::max)
+}
+
+/// Smooth π-pulse-like control Ω(t) = 3 sin(πt) on [0, 1]
+fn pulse(n: usize) -> Vec<f64> {
+    (0..n)
+        .map(|k| 3.0 * (std::f64::consts::PI * (k as f64 + 0.5) / n as f64).sin())
+        .collect()
+}
+
+fn main() {
+    println!("Checkpointing Long Time Loops");
+    println!("=============================\n");
+
+    let x0 = [1.0, 0.0, 0.0, 0.0];
+    let loss = |x: &[f64; 4]| (infidelity_of(x), [0.0, 0.0, -2.0 * x[2], -2.0 * x[3]]);
+
+    // 1. Correctness on a short loop, every budget
+    let n_short = 1000;
+    let dt = 1.0 / n_short as f64;
+    let controls = pulse(n_short);
+    let step = |x: &mut [f64; 4], u: f64| apply_exact_step(x, u, dt);
+    let step_vjp = |x: &[f64; 4], u: f64, lambda: &[f64; 4]| {
+        let mut dx = [0.0; 4];
+        let (_, du) = d_step_probe(x, &mut dx, u, dt, lambda, 1.0);
+        (dx, du)
+    };
+    println!("1. {n_short} steps: checkpointed vs unrolled Enzyme gradient");
+    let mut unrolled = vec![0.0; n_short];
+    let unrolled_value = d_infidelity_steps(&controls, &mut unrolled, dt, 1.0);
+    println!("   Unrolled: L = {unrolled_value:.12}");
+    println!(
+        "   {:>7} {:>6} {:>14} {:>9} {:>12}",
+        "budget", "peak", "forward steps", "optimal", "max |Δgrad|"
+    );
+    let mut short_err: f64 = 0.0;
+    let mut optimal = true;
+    for budget in [n_short + 1, 100, 20, 10, 5, 2] {
+        let mut grad = vec![0.0; n_short];
+        let (value, stats) =
+            checkpointed_gradient(&step, &step_vjp, &loss, &x0, &controls, budget, &mut grad);
+        let err = max_abs_diff(&grad, &unrolled).max((value - unrolled_value).abs());
+        short_err = short_err.max(err);
+        let best = optimal_forward_steps(budget, n_short);
+        optimal &= stats.peak_states <= budget && stats.forward_steps == best;
+        println!(
+            "   {budget:>7} {:>6} {:>14} {best:>9} {err:>12.2e}",
+            stats.peak_states, stats.forward_steps
+        );
+    }
+    // An empty loop returns the loss at x₀ without stepping
+    let (empty_value, empty_stats) =
+        checkpointed_gradient(&step, &step_vjp, &loss, &x0, &[], 10, &mut []);
+    short_err = short_err.max((empty_value - loss(&x0).0).abs());
+    optimal &= empty_stats.forward_steps == 0;
+    println!("   0 steps: L = L(x₀) = {empty_value}");
+
+    // 2. A long loop: memory vs time for different budgets
+    let dt = 1.0 / N_LONG as f64;
+    let controls = pulse(N_LONG);
+    let step = |x: &mut [f64; 4], u: f64| apply_exact_step(x, u, dt);
+    let step_vjp = |x: &[f64; 4], u: f64, lambda: &[f64; 4]| {
+        let mut dx = [0.0; 4];
+        let (_, du) = d_step_probe(x, &mut dx, u, dt, lambda, 1.0);
+        (dx, du)
+    };
+    let state_bytes = std::mem::size_of::<[f64; 4]>();
+    println!("\n2. {N_LONG} steps: memory vs time");
+    let mut unrolled = vec![0.0; N_LONG];
+    let t_unrolled = time_per_call(|| {
+        d_infidelity_steps(&controls, &mut unrolled, dt, 1.0);
+    });
+    println!(
+        "   {:>18} {:>9} {:>11} {:>14} {:>10}",
+        "strategy", "states", "memory", "forward steps", "time"
+    );
+    println!(
+        "   {:>18} {:>9} {:>11} {:>14} {:>7.1} ms",
+        "unrolled Enzyme",
+        "all",
+        "tape",
+        N_LONG,
+        t_unrolled * 1e3
+    );
+    let mut long_err: f64 = 0.0;
+    for budget in [N_LONG + 1, 1000, 100, 20, 10] {
+        let mut grad = vec![0.0; N_LONG];
+        let mut stats = None;
+        let t = time_per_call(|| {
+            stats = Some(
+                checkpointed_gradient(&step, &step_vjp, &loss, &x0, &controls, budget, &mut grad).1,
+            );
+        });
+        let stats = stats.unwrap();
+        long_err = long_err.max(max_abs_diff(&grad, &unrolled));
+        println!(
+            "   {:>18} {:>9} {:>8.1} KB {:>14} {:>7.1} ms",
+            format!("budget {budget}"),
+            stats.peak_states,
+            (stats.peak_states * state_bytes) as f64 / 1024.0,
+            stats.forward_steps,
+            t * 1e3
+        );
+    }
+    println!(
+        "   The unrolled tape holds at least one state per step, like budget {}.",
+        N_LONG + 1
+    );
+    println!("   Each 10× cut in the budget adds only a few evaluations per step.");
+
+    println!("\n--- Verification ---");
+    if short_err < 1e-12 {
+        println!(
+            "PASS: checkpointed gradients match the unrolled Enzyme gradient for every budget"
+        );
+    } else {
+        println!("FAIL: checkpointed gradient error {short_err:.2e}");
+    }
+    if optimal {
+        println!(
+            "PASS: peak states stay within the budget with the optimal number of step evaluations"
+        );
+    } else {
+        println!("FAIL: budget or repetition bound exceeded");
+    }
+    if long_err < 1e-10 {
+        println!("PASS: {N_LONG}-step gradients agree with the unrolled gradient");
+    } else {
+        println!("FAIL: long-loop gradient error {long_err:.2e}");
+    }
+}
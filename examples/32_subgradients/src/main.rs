@@ -0,0 +1,260 @@
+//! Example 32: Subgradient Conventions at Kinks
+//!
+//! AD differentiates the branch that actually runs, so at a kink the derivative
+//! is whatever the comparison happens to select: `relu` in example 12 reports
+//! 0 at x = 0 because `x > 0.0` is false there, while a hand-written
+//! `if x < 0.0 { -x } else { x }` reports +1. Both are valid subgradients, but
+//! the choice is accidental.
+//!
+//! This example provides relu, abs, max, min, clamp and hard sigmoid with an
+//! explicit `Kink` argument choosing the derivative at exactly the kink:
+//!
+//! - `Kink::Left`:  the derivative of the piece left of the kink
+//! - `Kink::Right`: the derivative of the piece right of the kink
+//! - `Kink::Mid`:   the average of the two (0 for abs, an even split for max/min)
+//!
+//! Run with: RUSTFLAGS="-Z autodiff=Enable" cargo +enzyme run -p subgradients
+
+#![feature(autodiff)]
+
+use std::autodiff::autodiff_reverse;
+
+/// Which derivative a primitive reports when its input sits exactly on a kink
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Kink {
+    Left,
+    Right,
+    Mid,
+}
+
+/// Selects the piece of a function with a kink at `at`. Away from the kink the
+/// comparison decides; exactly at it, `kink` does. Both pieces agree in value
+/// at the kink, so only the derivative depends on the choice.
+fn select_piece(x: f64, at: f64, left: f64, right: f64, kink: Kink) -> f64 {
+    if x < at {
+        left
+    } else if x > at {
+        right
+    } else {
+        match kink {
+            Kink::Left => left,
+            Kink::Right => right,
+            Kink::Mid => 0.5 * (left + right),
+        }
+    }
+}
+
+/// max(0, x); derivative at 0 is 0 (Left), 1 (Right) or 1/2 (Mid)
+#[autodiff_reverse(d_relu, Active, Const, Active)]
+fn relu(x: f64, kink: Kink) -> f64 {
+    select_piece(x, 0.0, 0.0, x, kink)
+}
+
+/// |x|; derivative at 0 is -1 (Left), 1 (Right) or 0 (Mid)
+#[autodiff_reverse(d_abs, Active, Const, Active)]
+fn abs(x: f64, kink: Kink) -> f64 {
+    select_piece(x, 0.0, -x, x, kink)
+}
+
+/// max(a, b); the kink is a = b, where "left" means a < b. At a tie the
+/// gradient goes to b (Left), to a (Right) or half to each (Mid)
+#[autodiff_reverse(d_maximum, Active, Active, Const, Active)]
+fn maximum(a: f64, b: f64, kink: Kink) -> f64 {
+    select_piece(a - b, 0.0, b, a, kink)
+}
+
+/// min(a, b); at a tie the gradient goes to a (Left), to b (Right) or half to
+/// each (Mid)
+#[autodiff_reverse(d_minimum, Active, Active, Const, Active)]
+fn minimum(a: f64, b: f64, kink: Kink) -> f64 {
+    select_piece(a - b, 0.0, a, b, kink)
+}
+
+/// x limited to [lo, hi]; `kink` applies at both bounds, so at lo the
+/// derivative is 0 (Left), 1 (Right) or 1/2 (Mid), and at hi 1, 0 or 1/2
+#[autodiff_reverse(d_clamp, Active, Const, Const, Const, Active)]
+fn clamp(x: f64, lo: f64, hi: f64, kink: Kink) -> f64 {
+    let upper = select_piece(x, hi, x, hi, kink);
+    select_piece(x, lo, lo, upper, kink)
+}
+
+/// clamp(0.2x + 0.5, 0, 1), with kinks at x = ±2.5
+#[autodiff_reverse(d_hard_sigmoid, Active, Const, Active)]
+fn hard_sigmoid(x: f64, kink: Kink) -> f64 {
+    clamp(0.2 * x + 0.5, 0.0, 1.0, kink)
+}
+
+/// Mean squared error of a single neuron y = relu(w·x + b)
+#[autodiff_reverse(d_neuron_loss, Active, Active, Const, Const, Const, Active)]
+fn neuron_loss(w: f64, b: f64, xs: &[f64], ys: &[f64], kink: Kink) -> f64 {
+    let mut sum = 0.0;
+    let mut i = 0;
+    while i < xs.len() {
+        let r = relu(w * xs[i] + b, kink) - ys[i];
+        sum += r * r;
+        i += 1;
+    }
+    sum / xs.len() as f64
+}
+
+// ---------------------------------------------------------------------------
+// Checks (plain Rust, not differentiated)
+// ---------------------------------------------------------------------------
+
+const KINKS: [Kink; 3] = [Kink::Left, Kink::Right, Kink::Mid];
+
+/// Central difference, step 1e-6
+fn fd(f: impl Fn(f64) -> f64, x: f64) -> f64 {
+    let step = 1e-6;
+    (f(x + step) - f(x - step)) / (2.0 * step)
+}
+
+fn main() {
+    println!("Subgradient Conventions at Kinks");
+    println!("================================\n");
+
+    // 1. Derivatives exactly at the kinks
+    println!("1. Derivatives at the kink for each convention");
+    println!(
+        "   {:<24} {:>10} {:>10} {:>10}",
+        "primitive", "Left", "Right", "Mid"
+    );
+    // (name, derivatives for Left, Right, Mid, expected)
+    let cases: [(&str, [f64; 3], [f64; 3]); 8] = [
+        (
+            "relu'(0)",
+            KINKS.map(|k| d_relu(0.0, k, 1.0).1),
+            [0.0, 1.0, 0.5],
+        ),
+        (
+            "abs'(0)",
+            KINKS.map(|k| d_abs(0.0, k, 1.0).1),
+            [-1.0, 1.0, 0.0],
+        ),
+        (
+            "∂max(a, a)/∂a",
+            KINKS.map(|k| d_maximum(1.5, 1.5, k, 1.0).1),
+            [0.0, 1.0, 0.5],
+        ),
+        (
+            "∂max(a, a)/∂b",
+            KINKS.map(|k| d_maximum(1.5, 1.5, k, 1.0).2),
+            [1.0, 0.0, 0.5],
+        ),
+        (
+            "∂min(a, a)/∂a",
+            KINKS.map(|k| d_minimum(1.5, 1.5, k, 1.0).1),
+            [1.0, 0.0, 0.5],
+        ),
+        (
+            "clamp'(lo)",
+            KINKS.map(|k| d_clamp(-1.0, -1.0, 1.0, k, 1.0).1),
+            [0.0, 1.0, 0.5],
+        ),
+        (
+            "clamp'(hi)",
+            KINKS.map(|k| d_clamp(1.0, -1.0, 1.0, k, 1.0).1),
+            [1.0, 0.0, 0.5],
+        ),
+        (
+            "hard_sigmoid'(-2.5)",
+            KINKS.map(|k| d_hard_sigmoid(-2.5, k, 1.0).1),
+            [0.0, 0.2, 0.1],
+        ),
+    ];
+    let mut kinks_ok = true;
+    for (name, got, expected) in &cases {
+        kinks_ok &= got.iter().zip(expected).all(|(g, e)| (g - e).abs() < 1e-15);
+        println!("   {name:<24} {:>10} {:>10} {:>10}", got[0], got[1], got[2]);
+    }
+    println!("   (hard_sigmoid'(+2.5) is the mirror image: 0.2, 0, 0.1)");
+
+    // 2. Away from the kinks every convention agrees with finite differences
+    println!("\n2. Away from the kinks: all conventions vs finite differences");
+    let mut smooth_err: f64 = 0.0;
+    for x in [-3.0, -1.7, -0.4, 0.3, 0.9, 2.2, 4.0] {
+        for kink in KINKS {
+            let pairs = [
+                (d_relu(x, kink, 1.0).1, fd(|t| relu(t, kink), x)),
+                (d_abs(x, kink, 1.0).1, fd(|t| abs(t, kink), x)),
+                (
+                    d_maximum(x, 0.5, kink, 1.0).1,
+                    fd(|t| maximum(t, 0.5, kink), x),
+                ),
+                (
+                    d_minimum(x, 0.5, kink, 1.0).1,
+                    fd(|t| minimum(t, 0.5, kink), x),
+                ),
+                (
+                    d_clamp(x, -1.0, 1.0, kink, 1.0).1,
+                    fd(|t| clamp(t, -1.0, 1.0, kink), x),
+                ),
+                (
+                    d_hard_sigmoid(x, kink, 1.0).1,
+                    fd(|t| hard_sigmoid(t, kink), x),
+                ),
+            ];
+            for (ad, num) in pairs {
+                smooth_err = smooth_err.max((ad - num).abs());
+            }
+        }
+    }
+    println!("   max |AD - FD| over 7 points × 6 primitives × 3 conventions = {smooth_err:.2e}");
+
+    // 3. Training from an exactly-zero start
+    println!("\n3. Training relu(w·x + b) from w = b = 0, target relu(2x)");
+    let xs = [-1.0, -0.5, 0.5, 1.0];
+    let ys = xs.map(|x: f64| (2.0 * x).max(0.0));
+    let lr = 0.5;
+    let mut final_losses = [0.0; 3];
+    for (i, kink) in KINKS.into_iter().enumerate() {
+        let (mut w, mut b) = (0.0, 0.0);
+        let mut loss = 0.0;
+        for _ in 0..500 {
+            let (l, dw, db) = d_neuron_loss(w, b, &xs, &ys, kink, 1.0);
+            loss = l;
+            w -= lr * dw;
+            b -= lr * db;
+        }
+        final_losses[i] = loss;
+        println!("   {kink:?}: w = {w:.6}, b = {b:.6}, loss = {loss:.3e}");
+    }
+    println!("   Every pre-activation starts exactly at the kink. With Left the");
+    println!("   neuron is dead from the first step; Right and Mid train.");
+
+    // 4. L1 subgradient descent from w = 0
+    println!("\n4. Subgradient descent on |w| from w = 0");
+    let mut l1_final = [0.0; 3];
+    for (i, kink) in KINKS.into_iter().enumerate() {
+        let mut w: f64 = 0.0;
+        for _ in 0..5 {
+            w -= 0.1 * d_abs(w, kink, 1.0).1;
+        }
+        l1_final[i] = w;
+        println!("   {kink:?}: w after 5 steps = {w:+.1}");
+    }
+    println!("   Mid returns the minimum-norm subgradient 0 and stays at the minimum.");
+
+    println!("\n--- Verification ---");
+    if kinks_ok {
+        println!("PASS: every primitive reports the documented derivative at its kink");
+    } else {
+        println!("FAIL: a kink derivative differs from its convention");
+    }
+    if smooth_err < 1e-8 {
+        println!("PASS: away from kinks all conventions match finite differences");
+    } else {
+        println!("FAIL: smooth-region error {smooth_err:.2e}");
+    }
+    // The initial loss is mean(y²) = 1.25
+    if final_losses[0] == 1.25 && final_losses[1] < 1e-6 && final_losses[2] < 1e-6 {
+        println!("PASS: training from zero is stuck with Left and converges with Right and Mid");
+    } else {
+        println!("FAIL: final losses {final_losses:?}");
+    }
+    if l1_final[2] == 0.0 && l1_final[0] != 0.0 && l1_final[1] != 0.0 {
+        println!("PASS: Mid keeps |w| at its minimum; Left and Right step away from it");
+    } else {
+        println!("FAIL: L1 iterates {l1_final:?}");
+    }
+}
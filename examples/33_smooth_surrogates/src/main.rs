@@ -0,0 +1,351 @@
+//! Example 33: Smooth Surrogates for Non-Differentiable Operations
+//!
+//! Hard operations such as relu, clamp and step have zero gradient on whole
+//! regions: a clamped probability in the BCE loss (example 08) or a negative
+//! relu input (example 12) passes no signal back. Each surrogate here replaces
+//! one with a smooth function controlled by a temperature τ > 0:
+//!
+//! - softplus:     τ ln(1 + e^{x/τ})                  → relu(x)
+//! - smooth_abs:   √(x² + τ²) - τ                     → |x|
+//! - smooth_max:   τ ln Σ e^{xᵢ/τ}  (log-sum-exp)     → max(x)
+//! - smooth_min:   -τ ln Σ e^{-xᵢ/τ}                  → min(x)
+//! - soft_clamp:   lo + softplus(x - lo) - softplus(x - hi) → clamp(x, lo, hi)
+//! - soft_step:    sigmoid(x/τ)                       → step(x)
+//!
+//! As τ → 0 the values and the gradients converge to the hard versions away
+//! from the kinks; for larger τ the gradient is nonzero everywhere.
+//!
+//! Run with: RUSTFLAGS="-Z autodiff=Enable" cargo +enzyme run -p smooth_surrogates
+
+#![feature(autodiff)]
+
+use std::autodiff::autodiff_reverse;
+
+/// Series e^x: halve x until |x| ≤ 1/2, sum 20 terms, square back
+fn my_exp(x: f64) -> f64 {
+    let mut r = x;
+    let mut halvings = 0;
+    while (if r < 0.0 { -r } else { r }) > 0.5 {
+        r /= 2.0;
+        halvings += 1;
+    }
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    let mut k = 1;
+    while k < 20 {
+        term *= r / k as f64;
+        sum += term;
+        k += 1;
+    }
+    let mut i = 0;
+    while i < halvings {
+        sum *= sum;
+        i += 1;
+    }
+    sum
+}
+
+/// ln(1 + u) for u ≥ 0 as 2·atanh(z), z = u/(2 + u) ≤ 1; the series in z²
+/// converges quickly for the u ≤ 1 used here (z ≤ 1/3)
+fn my_ln1p(u: f64) -> f64 {
+    let z = u / (2.0 + u);
+    let z2 = z * z;
+    let mut sum = 0.0;
+    let mut power = z;
+    let mut k = 0;
+    while k < 20 {
+        sum += power / (2 * k + 1) as f64;
+        power *= z2;
+        k += 1;
+    }
+    2.0 * sum
+}
+
+/// Newton sqrt with range reduction into [0.25, 1]
+fn my_sqrt(x: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    let mut m = x;
+    let mut scale = 1.0;
+    while m > 1.0 {
+        m /= 4.0;
+        scale *= 2.0;
+    }
+    while m < 0.25 {
+        m *= 4.0;
+        scale /= 2.0;
+    }
+    let mut guess = 0.5 + 0.5 * m;
+    let mut i = 0;
+    while i < 8 {
+        guess = 0.5 * (guess + m / guess);
+        i += 1;
+    }
+    guess * scale
+}
+
+/// Logistic function without overflow
+fn sigmoid(x: f64) -> f64 {
+    if x >= 0.0 {
+        1.0 / (1.0 + my_exp(-x))
+    } else {
+        let e = my_exp(x);
+        e / (1.0 + e)
+    }
+}
+
+/// τ ln(1 + e^{x/τ}), computed as max(x, 0) + τ ln(1 + e^{-|x|/τ});
+/// derivative sigmoid(x/τ)
+#[autodiff_reverse(d_softplus, Active, Const, Active)]
+fn softplus(x: f64, tau: f64) -> f64 {
+    let (pos, neg_abs) = if x > 0.0 { (x, -x) } else { (0.0, x) };
+    pos + tau * my_ln1p(my_exp(neg_abs / tau))
+}
+
+/// √(x² + τ²) - τ; derivative x/√(x² + τ²)
+#[autodiff_reverse(d_smooth_abs, Active, Const, Active)]
+fn smooth_abs(x: f64, tau: f64) -> f64 {
+    my_sqrt(x * x + tau * tau) - tau
+}
+
+/// sign·τ ln Σ e^{sign·xᵢ/τ}, shifted by the hard extreme for stability. The
+/// shift cancels in the gradient, which is the softmax of sign·x/τ.
+fn log_sum_exp(xs: &[f64], tau: f64, sign: f64) -> f64 {
+    let mut m = sign * xs[0];
+    let mut i = 1;
+    while i < xs.len() {
+        if sign * xs[i] > m {
+            m = sign * xs[i];
+        }
+        i += 1;
+    }
+    let mut sum = 0.0;
+    let mut i = 0;
+    while i < xs.len() {
+        sum += my_exp((sign * xs[i] - m) / tau);
+        i += 1;
+    }
+    // sum ∈ [1, n]; ln(sum) = ln(1 + (sum - 1)) in steps of at most ln 2
+    let mut log_sum = 0.0;
+    while sum > 2.0 {
+        sum /= 2.0;
+        log_sum += std::f64::consts::LN_2;
+    }
+    sign * (m + tau * (log_sum + my_ln1p(sum - 1.0)))
+}
+
+/// Log-sum-exp smooth maximum; max(x) ≤ smooth_max ≤ max(x) + τ ln n
+#[autodiff_reverse(d_smooth_max, Duplicated, Const, Active)]
+fn smooth_max(xs: &[f64], tau: f64) -> f64 {
+    log_sum_exp(xs, tau, 1.0)
+}
+
+/// Log-sum-exp smooth minimum; min(x) - τ ln n ≤ smooth_min ≤ min(x)
+#[autodiff_reverse(d_smooth_min, Duplicated, Const, Active)]
+fn smooth_min(xs: &[f64], tau: f64) -> f64 {
+    log_sum_exp(xs, tau, -1.0)
+}
+
+/// lo + softplus(x - lo) - softplus(x - hi); derivative
+/// sigmoid((x - lo)/τ) - sigmoid((x - hi)/τ)
+#[autodiff_reverse(d_soft_clamp, Active, Const, Const, Const, Active)]
+fn soft_clamp(x: f64, lo: f64, hi: f64, tau: f64) -> f64 {
+    lo + softplus(x - lo, tau) - softplus(x - hi, tau)
+}
+
+/// sigmoid(x/τ); derivative sigmoid'(x/τ)/τ, a bump of width ~τ around 0
+#[autodiff_reverse(d_soft_step, Active, Const, Active)]
+fn soft_step(x: f64, tau: f64) -> f64 {
+    sigmoid(x / tau)
+}
+
+/// Mean squared error of the neuron y = act(w·x + b), where act is softplus
+/// with temperature τ, or the hard relu for τ = 0
+#[autodiff_reverse(d_neuron_loss, Active, Active, Const, Const, Const, Active)]
+fn neuron_loss(w: f64, b: f64, xs: &[f64], ys: &[f64], tau: f64) -> f64 {
+    let mut sum = 0.0;
+    let mut i = 0;
+    while i < xs.len() {
+        let pre = w * xs[i] + b;
+        let act = if tau > 0.0 {
+            softplus(pre, tau)
+        } else if pre > 0.0 {
+            pre
+        } else {
+            0.0
+        };
+        let r = act - ys[i];
+        sum += r * r;
+        i += 1;
+    }
+    sum / xs.len() as f64
+}
+
+// ---------------------------------------------------------------------------
+// Hard references and checks (plain Rust, not differentiated)
+// ---------------------------------------------------------------------------
+
+/// A surrogate evaluated at one point: (hard value, hard derivative,
+/// surrogate value, surrogate derivative from Enzyme)
+type Comparison = (f64, f64, f64, f64);
+
+/// Sample points, all at least 0.1 away from every kink
+const POINTS: [f64; 8] = [-2.0, -1.3, -0.6, -0.1, 0.1, 0.4, 0.75, 2.5];
+
+/// Central difference, step 1e-6
+fn fd(f: impl Fn(f64) -> f64, x: f64) -> f64 {
+    let step = 1e-6;
+    (f(x + step) - f(x - step)) / (2.0 * step)
+}
+
+fn compare(name: &str, x: f64, tau: f64) -> Comparison {
+    let (lo, hi) = (-1.0, 0.5);
+    // Vector inputs for max/min: x competes with fixed entries
+    let others = [0.25, -0.35];
+    let vec = [x, others[0], others[1]];
+    let mut grad = [0.0; 3];
+    match name {
+        "softplus" => (
+            x.max(0.0),
+            if x > 0.0 { 1.0 } else { 0.0 },
+            softplus(x, tau),
+            d_softplus(x, tau, 1.0).1,
+        ),
+        "smooth_abs" => (
+            x.abs(),
+            x.signum(),
+            smooth_abs(x, tau),
+            d_smooth_abs(x, tau, 1.0).1,
+        ),
+        "smooth_max" => {
+            let value = d_smooth_max(&vec, &mut grad, tau, 1.0);
+            let hard = vec.iter().cloned().fold(f64::MIN, f64::max);
+            (hard, if x == hard { 1.0 } else { 0.0 }, value, grad[0])
+        }
+        "smooth_min" => {
+            let value = d_smooth_min(&vec, &mut grad, tau, 1.0);
+            let hard = vec.iter().cloned().fold(f64::MAX, f64::min);
+            (hard, if x == hard { 1.0 } else { 0.0 }, value, grad[0])
+        }
+        "soft_clamp" => (
+            x.clamp(lo, hi),
+            if x > lo && x < hi { 1.0 } else { 0.0 },
+            soft_clamp(x, lo, hi, tau),
+            d_soft_clamp(x, lo, hi, tau, 1.0).1,
+        ),
+        _ => (
+            if x > 0.0 { 1.0 } else { 0.0 },
+            0.0,
+            soft_step(x, tau),
+            d_soft_step(x, tau, 1.0).1,
+        ),
+    }
+}
+
+fn surrogate_value(name: &str, x: f64, tau: f64) -> f64 {
+    match name {
+        "softplus" => softplus(x, tau),
+        "smooth_abs" => smooth_abs(x, tau),
+        "smooth_max" => smooth_max(&[x, 0.25, -0.35], tau),
+        "smooth_min" => smooth_min(&[x, 0.25, -0.35], tau),
+        "soft_clamp" => soft_clamp(x, -1.0, 0.5, tau),
+        _ => soft_step(x, tau),
+    }
+}
+
+const NAMES: [&str; 6] = [
+    "softplus",
+    "smooth_abs",
+    "smooth_max",
+    "smooth_min",
+    "soft_clamp",
+    "soft_step",
+];
+
+fn main() {
+    println!("Smooth Surrogates for Non-Differentiable Operations");
+    println!("===================================================\n");
+
+    // 1. Enzyme gradients vs finite differences at a moderate temperature
+    println!("1. Surrogate gradients vs finite differences at τ = 0.5");
+    let mut fd_err: f64 = 0.0;
+    for name in NAMES {
+        let err = POINTS
+            .iter()
+            .map(|&x| {
+                let (_, _, _, ad) = compare(name, x, 0.5);
+                (ad - fd(|t| surrogate_value(name, t, 0.5), x)).abs()
+            })
+            .fold(0.0, f64::max);
+        fd_err = fd_err.max(err);
+        println!("   {name:<11} max |AD - FD| = {err:.2e}");
+    }
+
+    // 2. Convergence to the hard functions as τ → 0
+    println!(
+        "\n2. Distance to the hard function over {} points",
+        POINTS.len()
+    );
+    println!(
+        "   {:<11} {:>7} {:>14} {:>14}",
+        "surrogate", "τ", "max |Δvalue|", "max |Δgrad|"
+    );
+    let temperatures = [1.0, 0.1, 0.01, 0.001];
+    let mut converges = true;
+    for name in NAMES {
+        let mut previous = (f64::INFINITY, f64::INFINITY);
+        for tau in temperatures {
+            let (mut dv, mut dg): (f64, f64) = (0.0, 0.0);
+            for &x in &POINTS {
+                let (hard, hard_grad, value, grad) = compare(name, x, tau);
+                dv = dv.max((value - hard).abs());
+                dg = dg.max((grad - hard_grad).abs());
+            }
+            // The soft_step bump grows as 1/τ before it narrows past the
+            // sample points, so only the last step must shrink both errors
+            if tau < 0.01 {
+                converges &= dv <= previous.0 && dg <= previous.1;
+            }
+            previous = (dv, dg);
+            println!("   {name:<11} {tau:>7} {dv:>14.3e} {dg:>14.3e}");
+        }
+        converges &= previous.0 < 2e-3 && previous.1 < 1e-3;
+    }
+
+    // 3. Reviving a dead relu neuron
+    println!("\n3. Fitting y = 2x with act(w·x + b) from w = -1, b = -0.5");
+    let xs = [0.5, 1.0, 1.5, 2.0];
+    let ys = xs.map(|x| 2.0 * x);
+    let (hard_loss, hard_dw, hard_db) = d_neuron_loss(-1.0, -0.5, &xs, &ys, 0.0, 1.0);
+    println!("   Hard relu: loss = {hard_loss:.4}, gradient = ({hard_dw}, {hard_db})");
+    let (mut w, mut b) = (-1.0, -0.5);
+    let lr = 0.05;
+    for iter in 0..600 {
+        // Anneal τ from 1 down to 10⁻³
+        let tau = (0.99_f64.powi(iter)).max(1e-3);
+        let (_, dw, db) = d_neuron_loss(w, b, &xs, &ys, tau, 1.0);
+        w -= lr * dw;
+        b -= lr * db;
+    }
+    let revived = neuron_loss(w, b, &xs, &ys, 0.0);
+    println!("   Softplus with τ annealed 1 → 10⁻³: w = {w:.4}, b = {b:.4}");
+    println!("   Hard relu loss after training: {revived:.2e}");
+
+    println!("\n--- Verification ---");
+    if fd_err < 1e-7 {
+        println!("PASS: surrogate gradients match finite differences");
+    } else {
+        println!("FAIL: surrogate gradient error {fd_err:.2e}");
+    }
+    if converges {
+        println!("PASS: values and gradients converge to the hard functions as τ → 0");
+    } else {
+        println!("FAIL: a surrogate does not converge monotonically");
+    }
+    if hard_dw == 0.0 && hard_db == 0.0 && revived < 1e-4 {
+        println!("PASS: the hard relu neuron is dead; the annealed surrogate trains it");
+    } else {
+        println!("FAIL: neuron training ended at loss {revived:.2e}");
+    }
+}
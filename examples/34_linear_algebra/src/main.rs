@@ -0,0 +1,620 @@
+//! Example 34: Dense Linear Algebra Kernels
+//!
+//! Example 09 unrolls a 2×2 matrix-vector product by hand. This example
+//! provides general kernels on row-major slices, where element (i, j) of an
+//! m×n matrix sits at i·n + j:
+//!
+//! - `matmul`:           C = A B
+//! - `matvec`:           y = A x
+//! - `outer`:            C = x yᵀ
+//! - `transpose`:        T = Aᵀ
+//! - `solve_triangular`: x = T⁻¹ b for lower or upper triangular T
+//! - `cholesky`:         A = L Lᵀ for symmetric positive definite A
+//!
+//! Each kernel is differentiated through a scalar probe ⟨C̄, kernel(...)⟩,
+//! whose gradient is the vector-Jacobian product for the output adjoint C̄.
+//! The results are checked against the known adjoint formulas, such as
+//! Ā = C̄ Bᵀ and B̄ = Aᵀ C̄ for the matrix product.
+//!
+//! Run with: RUSTFLAGS="-Z autodiff=Enable" cargo +enzyme run -p linear_algebra
+
+#![feature(autodiff)]
+
+use std::autodiff::autodiff_reverse;
+
+/// Largest dimension of the probes' stack buffers. The kernels themselves
+/// work on slices of any size; each probe asserts its shapes fit.
+const MAX_DIM: usize = 6;
+const MAX_BUF: usize = MAX_DIM * MAX_DIM;
+
+/// Which half of a triangular matrix holds its entries
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Triangle {
+    Lower,
+    Upper,
+}
+
+/// C = A B for A m×k and B k×n
+fn matmul(a: &[f64], b: &[f64], c: &mut [f64], m: usize, k: usize, n: usize) {
+    let mut i = 0;
+    while i < m {
+        let mut j = 0;
+        while j < n {
+            let mut sum = 0.0;
+            let mut p = 0;
+            while p < k {
+                sum += a[i * k + p] * b[p * n + j];
+                p += 1;
+            }
+            c[i * n + j] = sum;
+            j += 1;
+        }
+        i += 1;
+    }
+}
+
+/// y = A x for A m×n
+fn matvec(a: &[f64], x: &[f64], y: &mut [f64], m: usize, n: usize) {
+    let mut i = 0;
+    while i < m {
+        let mut sum = 0.0;
+        let mut j = 0;
+        while j < n {
+            sum += a[i * n + j] * x[j];
+            j += 1;
+        }
+        y[i] = sum;
+        i += 1;
+    }
+}
+
+/// C = x yᵀ for x of length m and y of length n
+fn outer(x: &[f64], y: &[f64], c: &mut [f64], m: usize, n: usize) {
+    let mut i = 0;
+    while i < m {
+        let mut j = 0;
+        while j < n {
+            c[i * n + j] = x[i] * y[j];
+            j += 1;
+        }
+        i += 1;
+    }
+}
+
+/// T = Aᵀ for A m×n, so T is n×m
+fn transpose(a: &[f64], t: &mut [f64], m: usize, n: usize) {
+    let mut i = 0;
+    while i < m {
+        let mut j = 0;
+        while j < n {
+            t[j * m + i] = a[i * n + j];
+            j += 1;
+        }
+        i += 1;
+    }
+}
+
+/// Solves T x = b by forward (Lower) or back (Upper) substitution. Only the
+/// selected triangle of the n×n matrix T is read.
+fn solve_triangular(t: &[f64], b: &[f64], x: &mut [f64], n: usize, triangle: Triangle) {
+    let mut step = 0;
+    while step < n {
+        let i = match triangle {
+            Triangle::Lower => step,
+            Triangle::Upper => n - 1 - step,
+        };
+        let mut sum = b[i];
+        let mut j = 0;
+        while j < n {
+            let known = match triangle {
+                Triangle::Lower => j < i,
+                Triangle::Upper => j > i,
+            };
+            if known {
+                sum -= t[i * n + j] * x[j];
+            }
+            j += 1;
+        }
+        x[i] = sum / t[i * n + i];
+        step += 1;
+    }
+}
+
+/// Newton sqrt with range reduction into [0.25, 1]
+fn my_sqrt(x: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    let mut m = x;
+    let mut scale = 1.0;
+    while m > 1.0 {
+        m /= 4.0;
+        scale *= 2.0;
+    }
+    while m < 0.25 {
+        m *= 4.0;
+        scale /= 2.0;
+    }
+    let mut guess = 0.5 + 0.5 * m;
+    let mut i = 0;
+    while i < 8 {
+        guess = 0.5 * (guess + m / guess);
+        i += 1;
+    }
+    guess * scale
+}
+
+/// Lower triangular L with A = L Lᵀ (Cholesky-Banachiewicz). Only the lower
+/// triangle of A is read, so its gradient lands there too. A must be
+/// symmetric positive definite.
+fn cholesky(a: &[f64], l: &mut [f64], n: usize) {
+    let mut i = 0;
+    while i < n {
+        let mut j = 0;
+        while j < n {
+            if j > i {
+                l[i * n + j] = 0.0;
+            } else {
+                let mut sum = a[i * n + j];
+                let mut p = 0;
+                while p < j {
+                    sum -= l[i * n + p] * l[j * n + p];
+                    p += 1;
+                }
+                l[i * n + j] = if i == j {
+                    my_sqrt(sum)
+                } else {
+                    sum / l[j * n + j]
+                };
+            }
+            j += 1;
+        }
+        i += 1;
+    }
+}
+
+/// Σᵢ uᵢ vᵢ over the first `len` entries
+fn dot(u: &[f64], v: &[f64], len: usize) -> f64 {
+    let mut sum = 0.0;
+    let mut i = 0;
+    while i < len {
+        sum += u[i] * v[i];
+        i += 1;
+    }
+    sum
+}
+
+// Probes: ⟨C̄, kernel(...)⟩ with the output adjoint C̄ passed as `Const`. The
+// gradient w.r.t. each `Duplicated` input is that input's adjoint. Dimensions
+// come from the slice lengths.
+
+/// ⟨C̄, A B⟩, with the inner dimension k giving A m×k and B k×n
+#[autodiff_reverse(d_matmul_probe, Duplicated, Duplicated, Const, Const, Active)]
+fn matmul_probe(a: &[f64], b: &[f64], c_bar: &[f64], k: usize) -> f64 {
+    assert!(
+        k >= 1 && a.len().is_multiple_of(k) && b.len().is_multiple_of(k),
+        "A or B is not a multiple of k = {k}"
+    );
+    let (m, n) = (a.len() / k, b.len() / k);
+    assert!(
+        m * n <= MAX_BUF && c_bar.len() == m * n,
+        "C̄ must be {m}×{n}, at most MAX_BUF"
+    );
+    let mut c = [0.0; MAX_BUF];
+    matmul(a, b, &mut c, m, k, n);
+    dot(c_bar, &c, m * n)
+}
+
+/// ⟨ȳ, A x⟩
+#[autodiff_reverse(d_matvec_probe, Duplicated, Duplicated, Const, Active)]
+fn matvec_probe(a: &[f64], x: &[f64], y_bar: &[f64]) -> f64 {
+    let (m, n) = (y_bar.len(), x.len());
+    assert!(
+        m <= MAX_DIM && a.len() == m * n,
+        "A must be {m}×{n} with m ≤ MAX_DIM"
+    );
+    let mut y = [0.0; MAX_DIM];
+    matvec(a, x, &mut y, m, n);
+    dot(y_bar, &y, m)
+}
+
+/// ⟨C̄, x yᵀ⟩
+#[autodiff_reverse(d_outer_probe, Duplicated, Duplicated, Const, Active)]
+fn outer_probe(x: &[f64], y: &[f64], c_bar: &[f64]) -> f64 {
+    let (m, n) = (x.len(), y.len());
+    assert!(
+        m * n <= MAX_BUF && c_bar.len() == m * n,
+        "C̄ must be {m}×{n}, at most MAX_BUF"
+    );
+    let mut c = [0.0; MAX_BUF];
+    outer(x, y, &mut c, m, n);
+    dot(c_bar, &c, m * n)
+}
+
+/// ⟨T̄, Aᵀ⟩ for A m×n
+#[autodiff_reverse(d_transpose_probe, Duplicated, Const, Const, Active)]
+fn transpose_probe(a: &[f64], t_bar: &[f64], m: usize) -> f64 {
+    assert!(
+        m >= 1 && a.len().is_multiple_of(m),
+        "A is not a multiple of m = {m} rows"
+    );
+    assert!(
+        a.len() <= MAX_BUF && t_bar.len() == a.len(),
+        "T̄ must match A, at most MAX_BUF"
+    );
+    let n = a.len() / m;
+    let mut t = [0.0; MAX_BUF];
+    transpose(a, &mut t, m, n);
+    dot(t_bar, &t, m * n)
+}
+
+/// ⟨x̄, T⁻¹ b⟩
+#[autodiff_reverse(d_solve_probe, Duplicated, Duplicated, Const, Const, Active)]
+fn solve_probe(t: &[f64], b: &[f64], x_bar: &[f64], triangle: Triangle) -> f64 {
+    let n = b.len();
+    assert!(
+        n <= MAX_DIM && t.len() == n * n && x_bar.len() == n,
+        "T must be {n}×{n} with n ≤ MAX_DIM"
+    );
+    let mut x = [0.0; MAX_DIM];
+    solve_triangular(t, b, &mut x, n, triangle);
+    dot(x_bar, &x, n)
+}
+
+/// ⟨L̄, chol(A)⟩
+#[autodiff_reverse(d_cholesky_probe, Duplicated, Const, Const, Active)]
+fn cholesky_probe(a: &[f64], l_bar: &[f64], n: usize) -> f64 {
+    assert!(
+        n <= MAX_DIM && a.len() == n * n && l_bar.len() == n * n,
+        "A must be {n}×{n} with n ≤ MAX_DIM"
+    );
+    let mut l = [0.0; MAX_BUF];
+    cholesky(a, &mut l, n);
+    dot(l_bar, &l, n * n)
+}
+
+/// ½ bᵀ A⁻¹ b = ½ ‖L⁻¹ b‖² for A = L Lᵀ, the quadratic term of a Gaussian
+/// log-likelihood
+#[autodiff_reverse(d_quadratic_form, Duplicated, Const, Active)]
+fn quadratic_form(a: &[f64], b: &[f64]) -> f64 {
+    let n = b.len();
+    assert!(
+        n <= MAX_DIM && a.len() == n * n,
+        "A must be {n}×{n} with n ≤ MAX_DIM"
+    );
+    let mut l = [0.0; MAX_BUF];
+    cholesky(a, &mut l, n);
+    let mut z = [0.0; MAX_DIM];
+    solve_triangular(&l, b, &mut z, n, Triangle::Lower);
+    0.5 * dot(&z, &z, n)
+}
+
+/// Example 09's loss ½‖W x + b - target‖², with `matvec` in place of the
+/// unrolled product
+#[autodiff_reverse(d_linear_loss, Const, Duplicated, Duplicated, Const, Active)]
+fn linear_loss(x: &[f64], w: &[f64], bias: &[f64], target: &[f64]) -> f64 {
+    let (m, n) = (target.len(), x.len());
+    assert!(
+        m <= MAX_DIM && w.len() == m * n && bias.len() == m,
+        "W must be {m}×{n} with m ≤ MAX_DIM"
+    );
+    let mut y = [0.0; MAX_DIM];
+    matvec(w, x, &mut y, m, n);
+    let mut sum = 0.0;
+    let mut i = 0;
+    while i < m {
+        let r = y[i] + bias[i] - target[i];
+        sum += r * r;
+        i += 1;
+    }
+    0.5 * sum
+}
+
+// ---------------------------------------------------------------------------
+// Adjoint formulas and checks (plain Rust, not differentiated)
+// ---------------------------------------------------------------------------
+
+fn max_abs_diff(a: &[f64], b: &[f64]) -> f64 {
+    a.iter()
+        .zip(b)
+        .map(|(x, y)| (x - y).abs())
+        .fold(0.0, f64::max)
+}
+
+/// Deterministic pseudo-random numbers in [0, 1) (64-bit LCG)
+fn next_uniform(state: &mut u64) -> f64 {
+    *state = state
+        .wrapping_mul(6364136223846793005)
+        .wrapping_add(1442695040888963407);
+    (*state >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// `len` entries uniform in [-1, 1)
+fn random_vec(len: usize, rng: &mut u64) -> Vec<f64> {
+    (0..len).map(|_| 2.0 * next_uniform(rng) - 1.0).collect()
+}
+
+/// Random n×n triangular matrix with diagonal entries in [1, 2)
+fn random_triangular(n: usize, triangle: Triangle, rng: &mut u64) -> Vec<f64> {
+    let mut t = random_vec(n * n, rng);
+    for i in 0..n {
+        for j in 0..n {
+            let outside = match triangle {
+                Triangle::Lower => j > i,
+                Triangle::Upper => j < i,
+            };
+            if outside {
+                t[i * n + j] = 0.0;
+            }
+        }
+        t[i * n + i] = 1.0 + next_uniform(rng);
+    }
+    t
+}
+
+/// Random symmetric positive definite M Mᵀ + n·I
+fn random_spd(n: usize, rng: &mut u64) -> Vec<f64> {
+    let m = random_vec(n * n, rng);
+    let mut mt = vec![0.0; n * n];
+    transpose(&m, &mut mt, n, n);
+    let mut a = vec![0.0; n * n];
+    matmul(&m, &mt, &mut a, n, n, n);
+    for i in 0..n {
+        a[i * n + i] += n as f64;
+    }
+    a
+}
+
+fn transposed(a: &[f64], m: usize, n: usize) -> Vec<f64> {
+    let mut t = vec![0.0; m * n];
+    transpose(a, &mut t, m, n);
+    t
+}
+
+fn product(a: &[f64], b: &[f64], m: usize, k: usize, n: usize) -> Vec<f64> {
+    let mut c = vec![0.0; m * n];
+    matmul(a, b, &mut c, m, k, n);
+    c
+}
+
+/// X = T⁻¹ R for an n×n right-hand side R, one column at a time
+fn solve_columns(t: &[f64], r: &[f64], n: usize, triangle: Triangle) -> Vec<f64> {
+    let rt = transposed(r, n, n);
+    let mut xt = vec![0.0; n * n];
+    for j in 0..n {
+        solve_triangular(
+            t,
+            &rt[j * n..(j + 1) * n],
+            &mut xt[j * n..(j + 1) * n],
+            n,
+            triangle,
+        );
+    }
+    transposed(&xt, n, n)
+}
+
+/// Φ(X): the lower triangle of X with the diagonal halved
+fn phi(x: &[f64], n: usize) -> Vec<f64> {
+    let mut out = vec![0.0; n * n];
+    for i in 0..n {
+        for j in 0..i {
+            out[i * n + j] = x[i * n + j];
+        }
+        out[i * n + i] = 0.5 * x[i * n + i];
+    }
+    out
+}
+
+/// Cholesky adjoint: with S = L⁻ᵀ Φ(Lᵀ L̄) L⁻¹, the symmetric gradient is
+/// ½(S + Sᵀ). A kernel reading only the lower triangle of A collects the
+/// (i, j) and (j, i) contributions in entry (i, j): Ā = Φ(S + Sᵀ).
+fn cholesky_adjoint(l: &[f64], l_bar: &[f64], n: usize) -> Vec<f64> {
+    let lt = transposed(l, n, n);
+    let p = phi(&product(&lt, l_bar, n, n, n), n);
+    let y = solve_columns(&lt, &p, n, Triangle::Upper);
+    let st = solve_columns(&lt, &transposed(&y, n, n), n, Triangle::Upper);
+    let sym: Vec<f64> = st
+        .iter()
+        .zip(transposed(&st, n, n))
+        .map(|(a, b)| a + b)
+        .collect();
+    phi(&sym, n)
+}
+
+/// One kernel's Enzyme gradients next to its adjoint formulas
+struct Check {
+    name: &'static str,
+    formula: &'static str,
+    err: f64,
+}
+
+fn main() {
+    println!("Dense Linear Algebra Kernels");
+    println!("============================\n");
+
+    let mut rng = 20240605u64;
+    let (m, k, n) = (3, 4, 5);
+    let mut checks = Vec::new();
+
+    // matmul: Ā = C̄ Bᵀ, B̄ = Aᵀ C̄
+    let a = random_vec(m * k, &mut rng);
+    let b = random_vec(k * n, &mut rng);
+    let c_bar = random_vec(m * n, &mut rng);
+    let (mut da, mut db) = (vec![0.0; m * k], vec![0.0; k * n]);
+    d_matmul_probe(&a, &mut da, &b, &mut db, &c_bar, k, 1.0);
+    let err = max_abs_diff(&da, &product(&c_bar, &transposed(&b, k, n), m, n, k)).max(
+        max_abs_diff(&db, &product(&transposed(&a, m, k), &c_bar, k, m, n)),
+    );
+    checks.push(Check {
+        name: "matmul",
+        formula: "Ā = C̄Bᵀ, B̄ = AᵀC̄",
+        err,
+    });
+
+    // matvec: Ā = ȳ xᵀ, x̄ = Aᵀ ȳ
+    let a = random_vec(m * n, &mut rng);
+    let x = random_vec(n, &mut rng);
+    let y_bar = random_vec(m, &mut rng);
+    let (mut da, mut dx) = (vec![0.0; m * n], vec![0.0; n]);
+    d_matvec_probe(&a, &mut da, &x, &mut dx, &y_bar, 1.0);
+    let mut expected_da = vec![0.0; m * n];
+    outer(&y_bar, &x, &mut expected_da, m, n);
+    let mut expected_dx = vec![0.0; n];
+    matvec(&transposed(&a, m, n), &y_bar, &mut expected_dx, n, m);
+    checks.push(Check {
+        name: "matvec",
+        formula: "Ā = ȳxᵀ, x̄ = Aᵀȳ",
+        err: max_abs_diff(&da, &expected_da).max(max_abs_diff(&dx, &expected_dx)),
+    });
+
+    // outer: x̄ = C̄ y, ȳ = C̄ᵀ x
+    let x = random_vec(m, &mut rng);
+    let y = random_vec(n, &mut rng);
+    let c_bar = random_vec(m * n, &mut rng);
+    let (mut dx, mut dy) = (vec![0.0; m], vec![0.0; n]);
+    d_outer_probe(&x, &mut dx, &y, &mut dy, &c_bar, 1.0);
+    let mut expected_dx = vec![0.0; m];
+    matvec(&c_bar, &y, &mut expected_dx, m, n);
+    let mut expected_dy = vec![0.0; n];
+    matvec(&transposed(&c_bar, m, n), &x, &mut expected_dy, n, m);
+    checks.push(Check {
+        name: "outer",
+        formula: "x̄ = C̄y, ȳ = C̄ᵀx",
+        err: max_abs_diff(&dx, &expected_dx).max(max_abs_diff(&dy, &expected_dy)),
+    });
+
+    // transpose: Ā = T̄ᵀ
+    let a = random_vec(m * n, &mut rng);
+    let t_bar = random_vec(n * m, &mut rng);
+    let mut da = vec![0.0; m * n];
+    d_transpose_probe(&a, &mut da, &t_bar, m, 1.0);
+    checks.push(Check {
+        name: "transpose",
+        formula: "Ā = T̄ᵀ",
+        err: max_abs_diff(&da, &transposed(&t_bar, n, m)),
+    });
+
+    // Triangular solve x = T⁻¹ b: b̄ = T⁻ᵀ x̄, T̄ = -b̄ xᵀ on T's triangle
+    for (triangle, name) in [
+        (Triangle::Lower, "solve (lower)"),
+        (Triangle::Upper, "solve (upper)"),
+    ] {
+        let t = random_triangular(n, triangle, &mut rng);
+        let b = random_vec(n, &mut rng);
+        let x_bar = random_vec(n, &mut rng);
+        let (mut dt, mut db) = (vec![0.0; n * n], vec![0.0; n]);
+        d_solve_probe(&t, &mut dt, &b, &mut db, &x_bar, triangle, 1.0);
+        let mut x = vec![0.0; n];
+        solve_triangular(&t, &b, &mut x, n, triangle);
+        let other = match triangle {
+            Triangle::Lower => Triangle::Upper,
+            Triangle::Upper => Triangle::Lower,
+        };
+        let mut expected_db = vec![0.0; n];
+        solve_triangular(&transposed(&t, n, n), &x_bar, &mut expected_db, n, other);
+        let mut expected_dt = vec![0.0; n * n];
+        outer(&expected_db, &x, &mut expected_dt, n, n);
+        for i in 0..n {
+            for j in 0..n {
+                let inside = match triangle {
+                    Triangle::Lower => j <= i,
+                    Triangle::Upper => j >= i,
+                };
+                expected_dt[i * n + j] = if inside { -expected_dt[i * n + j] } else { 0.0 };
+            }
+        }
+        checks.push(Check {
+            name,
+            formula: "b̄ = T⁻ᵀx̄, T̄ = -tri(b̄xᵀ)",
+            err: max_abs_diff(&dt, &expected_dt).max(max_abs_diff(&db, &expected_db)),
+        });
+    }
+
+    // Cholesky: Ā = Φ(S + Sᵀ), S = L⁻ᵀ Φ(Lᵀ L̄) L⁻¹
+    let a = random_spd(n, &mut rng);
+    let mut l = vec![0.0; n * n];
+    cholesky(&a, &mut l, n);
+    let reconstruction = max_abs_diff(&product(&l, &transposed(&l, n, n), n, n, n), &a);
+    let l_bar = phi(&random_vec(n * n, &mut rng), n);
+    let mut da = vec![0.0; n * n];
+    d_cholesky_probe(&a, &mut da, &l_bar, n, 1.0);
+    checks.push(Check {
+        name: "cholesky",
+        formula: "Ā = Φ(S + Sᵀ)",
+        err: max_abs_diff(&da, &cholesky_adjoint(&l, &l_bar, n)),
+    });
+
+    println!("1. Enzyme gradients vs adjoint formulas (A {m}×{k}, B {k}×{n})");
+    println!("   {:<14} {:>10}   formula", "kernel", "max |Δ|");
+    for check in &checks {
+        println!(
+            "   {:<14} {:>10.2e}   {}",
+            check.name, check.err, check.formula
+        );
+    }
+    println!("   Cholesky reconstruction max |L Lᵀ - A| = {reconstruction:.2e}");
+
+    // 2. Composition: ∂(½ bᵀA⁻¹b)/∂A = -½ A⁻¹ b bᵀ A⁻¹, symmetric
+    println!("\n2. Composed kernels: ½ bᵀA⁻¹b through cholesky and solve");
+    let b = random_vec(n, &mut rng);
+    let mut da = vec![0.0; n * n];
+    let value = d_quadratic_form(&a, &mut da, &b, 1.0);
+    // u = A⁻¹ b = L⁻ᵀ L⁻¹ b
+    let mut z = vec![0.0; n];
+    solve_triangular(&l, &b, &mut z, n, Triangle::Lower);
+    let mut u = vec![0.0; n];
+    solve_triangular(&transposed(&l, n, n), &z, &mut u, n, Triangle::Upper);
+    let mut g = vec![0.0; n * n];
+    outer(&u, &u, &mut g, n, n);
+    // Lower-triangle convention: off-diagonal entries collect G_ij + G_ji
+    let mut expected = vec![0.0; n * n];
+    for i in 0..n {
+        for j in 0..i {
+            expected[i * n + j] = -g[i * n + j];
+        }
+        expected[i * n + i] = -0.5 * g[i * n + i];
+    }
+    let quad_err = max_abs_diff(&da, &expected);
+    let value_err = (value - 0.5 * dot(&b, &u, n)).abs();
+    println!("   ½ bᵀA⁻¹b = {value:.6}");
+    println!("   max |Ā - (-A⁻¹bbᵀA⁻¹ on the lower triangle)| = {quad_err:.2e}");
+
+    // 3. Example 09's linear layer through matvec
+    println!("\n3. Example 09's 2×2 linear layer with matvec");
+    let x = [1.0, 2.0];
+    let w = [0.5, 0.5, 0.5, 0.5];
+    let bias = [0.1, 0.1];
+    let target = [1.0, 2.0];
+    let (mut dw, mut dbias) = ([0.0; 4], [0.0; 2]);
+    let loss = d_linear_loss(&x, &w, &mut dw, &bias, &mut dbias, &target, 1.0);
+    // Residual r = W x + b - target; W̄ = r xᵀ, b̄ = r
+    let mut r = [0.0; 2];
+    matvec(&w, &x, &mut r, 2, 2);
+    for i in 0..2 {
+        r[i] += bias[i] - target[i];
+    }
+    let mut expected_dw = [0.0; 4];
+    outer(&r, &x, &mut expected_dw, 2, 2);
+    let layer_err = max_abs_diff(&dw, &expected_dw).max(max_abs_diff(&dbias, &r));
+    println!("   loss = {loss}");
+    println!("   ∂L/∂W = {dw:?}");
+    println!("   ∂L/∂b = {dbias:?}");
+
+    println!("\n--- Verification ---");
+    let kernel_err = checks.iter().map(|c| c.err).fold(0.0, f64::max);
+    if kernel_err < 1e-12 && reconstruction < 1e-12 {
+        println!("PASS: every kernel's gradient matches its adjoint formula");
+    } else {
+        println!("FAIL: kernel gradient error {kernel_err:.2e}");
+    }
+    if quad_err < 1e-12 && value_err < 1e-12 {
+        println!("PASS: cholesky + solve compose to -½ A⁻¹bbᵀA⁻¹");
+    } else {
+        println!("FAIL: composed gradient error {quad_err:.2e}");
+    }
+    if layer_err < 1e-14 {
+        println!("PASS: the matvec layer reproduces W̄ = r xᵀ and b̄ = r");
+    } else {
+        println!("FAIL: linear layer gradient error {layer_err:.2e}");
+    }
+}